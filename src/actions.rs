@@ -1,68 +1,584 @@
 use crate::{
-   audio::{audio_source, connection_data::ConnectionData, playback},
-   call_result,
+   api_tokens,
+   audio::{
+      audio_source::{self, InvalidClip},
+      clip_index,
+      clip_metadata::{self, ClipMetadata},
+      connection_data::ConnectionData,
+      playback, random,
+      trim::{InvalidTrim, Pcm},
+   },
+   call_result::{CallError, CallResult},
+   guild_config::{self, MAX_VOLUME},
+   history::{self, Origin, PlaySource},
+   locale::Message,
+   rate_limit, search,
 };
 use log::error;
 use reqwest::Client;
 use serenity::{
+   builder::{CreateActionRow, CreateButton},
    client::Context,
-   model::application::{CommandDataOptionValue, CommandInteraction},
+   model::{
+      application::{ButtonStyle, CommandDataOptionValue, CommandInteraction, ComponentInteraction},
+      channel::Attachment,
+      id::{GuildId, UserId},
+   },
 };
+use std::{
+   io::{Error, ErrorKind},
+   time::Duration,
+};
+use tokio::task;
+
+/// Prefix of the custom ID given to buttons that play the clip named by the rest of the ID.
+pub const PLAY_BUTTON_PREFIX: &str = "play:";
+
+// One row of buttons, the most a message can show side by side
+const MAX_SEARCH_RESULTS: usize = 5;
+// Discord rejects button labels longer than this
+const MAX_BUTTON_LABEL: usize = 80;
+// Discord rejects custom IDs longer than this, so clips with longer names are listed without a button
+const MAX_CUSTOM_ID: usize = 100;
+
+// How many entries each /stats leaderboard shows
+const STATS_LENGTH: usize = 5;
 
-pub async fn play(ctx: &Context, command: &CommandInteraction) -> String {
+// Generous for a sound clip, but keeps someone from filling the disk with a single upload
+const MAX_UPLOAD_BYTES: u32 = 10 * 1024 * 1024;
+
+pub async fn play(ctx: &Context, command: &CommandInteraction) -> CallResult {
    if let Some(connection) = ConnectionData::try_from_command(ctx, command).await {
       let option = &command.data.options.first().expect("Expected name option").value;
 
       if let CommandDataOptionValue::String(name) = option {
-         call_result::log_error_if_any(playback::play_file(ctx, name, connection).await).user_message
+         if let Err(wait) = check_rate_limit(ctx, command.user.id, &connection).await {
+            return Ok(rate_limit::wait_message(wait));
+         }
+         playback::play_file(ctx, name, connection, origin(command)).await
       } else {
-         "Cannot parse file name".to_string()
+         Err(CallError::Invalid(Message::new("cannot_parse_file_name")))
+      }
+   } else {
+      Err(CallError::NotInVoiceChannel)
+   }
+}
+
+pub async fn random(ctx: &Context, command: &CommandInteraction) -> CallResult {
+   let connection = match ConnectionData::try_from_command(ctx, command).await {
+      Some(connection) => connection,
+      None => return Err(CallError::NotInVoiceChannel),
+   };
+   let filter = command
+      .data
+      .options
+      .iter()
+      .find_map(|option| match (option.name.as_str(), &option.value) {
+         ("filter", CommandDataOptionValue::String(value)) => Some(value.as_str()),
+         _ => None,
+      });
+   if let Err(wait) = check_rate_limit(ctx, command.user.id, &connection).await {
+      return Ok(rate_limit::wait_message(wait));
+   }
+
+   match random::get_recent_picks(ctx).await.pick(connection.guild, filter).await {
+      Ok(Some(name)) => playback::play_file(ctx, &name, connection, origin(command)).await,
+      Ok(None) => Ok(match filter {
+         Some(filter) => Message::new("no_matching_clips").with("query", filter),
+         None => Message::new("no_clips"),
+      }),
+      Err(err) => Err(CallError::failed(Message::new("failed_list_clips"), err)),
+   }
+}
+
+pub async fn search(command: &CommandInteraction) -> (CallResult, Vec<CreateActionRow>) {
+   let guild_id = match command.guild_id {
+      Some(guild_id) => guild_id,
+      None => {
+         return (
+            Err(CallError::Invalid(Message::new("search_outside_server"))),
+            Vec::new(),
+         )
+      }
+   };
+   let query = command
+      .data
+      .options
+      .iter()
+      .find_map(|option| match (option.name.as_str(), &option.value) {
+         ("query", CommandDataOptionValue::String(value)) => Some(value.as_str()),
+         _ => None,
+      });
+   let query = match query {
+      Some(query) => query,
+      None => return (Err(CallError::Invalid(Message::new("cannot_parse_search"))), Vec::new()),
+   };
+
+   let clips = match audio_source::clips(&guild_id).await {
+      Ok(clips) => search::rank(clips, query),
+      Err(err) => {
+         return (
+            Err(CallError::failed(Message::new("failed_list_clips"), err)),
+            Vec::new(),
+         )
+      }
+   };
+   if clips.is_empty() {
+      return (Ok(Message::new("no_matching_clips").with("query", query)), Vec::new());
+   }
+
+   let matches = clips.into_iter().take(MAX_SEARCH_RESULTS).collect::<Vec<_>>();
+   let message = matches
+      .iter()
+      .map(|clip| {
+         if clip.tags.is_empty() {
+            format!("**{}**", clip.name)
+         } else {
+            format!("**{}** ({})", clip.name, clip.tags.join(", "))
+         }
+      })
+      .collect::<Vec<_>>()
+      .join("\n");
+   let buttons = matches
+      .iter()
+      .filter_map(|clip| {
+         let custom_id = format!("{PLAY_BUTTON_PREFIX}{}", clip.name);
+         (custom_id.chars().count() <= MAX_CUSTOM_ID).then(|| {
+            CreateButton::new(custom_id)
+               .label(clip.name.chars().take(MAX_BUTTON_LABEL).collect::<String>())
+               .style(ButtonStyle::Secondary)
+         })
+      })
+      .collect::<Vec<_>>();
+   // a row needs at least one button
+   let rows = if buttons.is_empty() {
+      Vec::new()
+   } else {
+      vec![CreateActionRow::Buttons(buttons)]
+   };
+   (Ok(Message::Plain(message)), rows)
+}
+
+/// Plays the clip behind a button from `/search` in the presser's voice channel.
+pub async fn play_button(ctx: &Context, component: &ComponentInteraction, name: &str) -> CallResult {
+   if let Some(connection) = ConnectionData::try_from_component(ctx, component).await {
+      if let Err(wait) = check_rate_limit(ctx, component.user.id, &connection).await {
+         return Ok(rate_limit::wait_message(wait));
       }
+      playback::play_file(
+         ctx,
+         name,
+         connection,
+         Origin::new(PlaySource::Command, component.user.id),
+      )
+      .await
    } else {
-      "You are not in a voice channel!".to_string()
+      Err(CallError::NotInVoiceChannel)
    }
 }
 
-pub async fn stop(ctx: &Context, command: &CommandInteraction) -> String {
+pub async fn tags(command: &CommandInteraction) -> CallResult {
+   let guild_id = match command.guild_id {
+      Some(guild_id) => guild_id,
+      None => return Err(CallError::Invalid(Message::new("tags_outside_server"))),
+   };
+
+   let mut clip = None;
+   let mut tags = None;
+   for option in &command.data.options {
+      match (option.name.as_str(), &option.value) {
+         ("clip", CommandDataOptionValue::String(value)) => clip = Some(value.as_str()),
+         ("tags", CommandDataOptionValue::String(value)) => tags = Some(parse_tags(value)),
+         _ => (),
+      }
+   }
+   let clip = match clip {
+      Some(clip) => clip,
+      None => return Err(CallError::Invalid(Message::new("cannot_parse_clip_name"))),
+   };
+
+   match (audio_source::exists(clip, &guild_id).await, tags) {
+      (Ok(false), _) => Err(CallError::ClipNotFound(clip.to_owned())),
+      (Ok(true), Some(tags)) => {
+         let saved = match clip_metadata::load(clip, &guild_id) {
+            Ok(metadata) => {
+               let metadata = ClipMetadata {
+                  tags: tags.clone(),
+                  ..metadata
+               };
+               clip_metadata::save(clip, &guild_id, &metadata).await
+            }
+            Err(err) => Err(err),
+         };
+         match saved {
+            Ok(_) if tags.is_empty() => Ok(Message::new("tags_removed").with("clip", clip)),
+            Ok(_) => Ok(Message::new("tags_set")
+               .with("clip", clip)
+               .with("tags", tags.join(", "))),
+            Err(err) => Err(CallError::failed(
+               Message::new("failed_save_tags").with("clip", clip),
+               err,
+            )),
+         }
+      }
+      (Ok(true), None) => match clip_metadata::load(clip, &guild_id) {
+         Ok(metadata) if metadata.tags.is_empty() => Ok(Message::new("no_tags").with("clip", clip)),
+         Ok(metadata) => Ok(Message::new("tagged")
+            .with("clip", clip)
+            .with("tags", metadata.tags.join(", "))),
+         Err(err) => Err(CallError::failed(
+            Message::new("failed_read_tags").with("clip", clip),
+            err,
+         )),
+      },
+      (Err(err), _) => Err(CallError::failed(
+         Message::new("failed_find_clip").with("clip", clip),
+         err,
+      )),
+   }
+}
+
+pub async fn stop(ctx: &Context, command: &CommandInteraction) -> CallResult {
    if let Some(connection) = ConnectionData::try_from_command(ctx, command).await {
-      call_result::log_error_if_any(playback::stop(ctx, connection).await).user_message
+      playback::stop(ctx, connection).await
    } else {
-      "You are not in a guild with the bot!".to_string()
+      Err(CallError::NotInVoiceChannel)
    }
 }
 
-pub async fn summon(ctx: &Context, command: &CommandInteraction) -> String {
-   let msg: String;
+pub async fn skip(ctx: &Context, command: &CommandInteraction) -> CallResult {
    if let Some(connection) = ConnectionData::try_from_command(ctx, command).await {
-      if let Ok(source) = audio_source::file("myman", &connection.guild).await {
-         if let Err(err) = playback::join_connection_and_play(ctx, connection, source, 1.0).await {
-            msg = "Bot failed to join your channel".to_string();
-            error!("Failed to join summon: {}", err);
-         } else {
-            msg = "Bot summoned".to_string();
-         }
-      } else if let Err(err) = playback::join_connection(ctx, connection).await {
-         msg = "Bot failed to join your channel".to_string();
-         error!("Failed to join summon: {}", err);
-      } else {
-         msg = "Bot summoned".to_string();
+      playback::skip(ctx, connection).await
+   } else {
+      Err(CallError::NotInVoiceChannel)
+   }
+}
+
+pub async fn queue(ctx: &Context, command: &CommandInteraction) -> CallResult {
+   if let Some(guild_id) = command.guild_id {
+      playback::queue(ctx, guild_id).await
+   } else {
+      Err(CallError::Invalid(Message::new("queue_outside_server")))
+   }
+}
+
+pub async fn volume(ctx: &Context, command: &CommandInteraction) -> CallResult {
+   let guild_id = match command.guild_id {
+      Some(guild_id) => guild_id,
+      None => return Err(CallError::Invalid(Message::new("volume_outside_server"))),
+   };
+
+   let mut level = None;
+   let mut clip = None;
+   for option in &command.data.options {
+      match (option.name.as_str(), &option.value) {
+         ("level", CommandDataOptionValue::Integer(value)) => level = Some(*value),
+         ("clip", CommandDataOptionValue::String(value)) => clip = Some(value.as_str()),
+         _ => (),
       }
-      msg
+   }
+
+   let max_percent = (MAX_VOLUME * 100.0) as i64;
+   match level {
+      Some(level) if !(0..=max_percent).contains(&level) => Err(CallError::Invalid(
+         Message::new("volume_out_of_range").with("max", max_percent),
+      )),
+      Some(level) => playback::set_volume(ctx, guild_id, clip, level as f32 / 100.0).await,
+      None => playback::volume(ctx, guild_id, clip).await,
+   }
+}
+
+pub async fn stats(command: &CommandInteraction) -> CallResult {
+   let guild_id = match command.guild_id {
+      Some(guild_id) => guild_id,
+      None => return Err(CallError::Invalid(Message::new("stats_outside_server"))),
+   };
+
+   let history = task::spawn_blocking(move || history::load(&guild_id))
+      .await
+      .unwrap_or_else(|err| Err(Error::other(err)))
+      .map_err(|err| CallError::failed(Message::new("failed_read_history"), err))?;
+   if history.is_empty() {
+      return Ok(Message::new("nothing_played"));
+   }
+
+   let stats = history::stats(&history, STATS_LENGTH);
+   let top_clips = stats
+      .top_clips
+      .iter()
+      .enumerate()
+      .map(|(rank, (clip, count))| format!("{}. **{clip}**: {count}", rank + 1))
+      .collect::<Vec<_>>()
+      .join("\n");
+   let top_users = stats
+      .top_users
+      .iter()
+      .enumerate()
+      .map(|(rank, (user_id, count))| format!("{}. <@{user_id}>: {count}", rank + 1))
+      .collect::<Vec<_>>()
+      .join("\n");
+   let recent = stats
+      .recent
+      .iter()
+      .map(|play| {
+         let message = match play.user {
+            Some(user_id) => Message::new("recent_play").with("user", format!("<@{user_id}>")),
+            None => Message::new("recent_play_anonymous"),
+         };
+         message
+            .with("clip", &play.clip)
+            .with_message("source", Message::new(source_key(play.source)))
+            .with("timestamp", play.timestamp)
+      })
+      .collect();
+
+   Ok(Message::new("stats")
+      .with("total", stats.total)
+      .with("top_clips", top_clips)
+      .with("top_users", top_users)
+      .with_message("recent", Message::Lines(recent)))
+}
+
+pub async fn summon(ctx: &Context, command: &CommandInteraction) -> CallResult {
+   if let Some(connection) = ConnectionData::try_from_command(ctx, command).await {
+      playback::join_connection_and_announce(ctx, connection, Some(command.user.id)).await?;
+      Ok(Message::new("summoned"))
    } else {
-      "You are not in a voice channel!".to_string()
+      Err(CallError::NotInVoiceChannel)
    }
 }
 
-pub async fn youtube(ctx: &Context, client: Client, command: &CommandInteraction) -> String {
+pub async fn youtube(ctx: &Context, client: Client, command: &CommandInteraction) -> CallResult {
    if let Some(connection) = ConnectionData::try_from_command(ctx, command).await {
       let option = &command.data.options.first().expect("Expected URL option").value;
 
       if let CommandDataOptionValue::String(url) = option {
-         call_result::log_error_if_any(playback::play_youtube(ctx, client, url, connection).await).user_message
+         if let Err(wait) = check_rate_limit(ctx, command.user.id, &connection).await {
+            return Ok(rate_limit::wait_message(wait));
+         }
+         playback::play_youtube(ctx, client, url, connection, origin(command)).await
       } else {
-         "Cannot parse YouTube URL".to_string()
+         Err(CallError::Invalid(Message::new("cannot_parse_url")))
       }
    } else {
-      "You are not in a voice channel!".to_string()
+      Err(CallError::NotInVoiceChannel)
+   }
+}
+
+pub async fn upload(client: Client, command: &CommandInteraction) -> CallResult {
+   let guild_id = match command.guild_id {
+      Some(guild_id) => guild_id,
+      None => return Err(CallError::Invalid(Message::new("upload_outside_server"))),
+   };
+
+   let mut name = None;
+   let mut attachment = None;
+   for option in &command.data.options {
+      match (option.name.as_str(), &option.value) {
+         ("name", CommandDataOptionValue::String(value)) => name = Some(value),
+         ("file", CommandDataOptionValue::Attachment(id)) => attachment = command.data.resolved.attachments.get(id),
+         _ => (),
+      }
+   }
+   let (name, attachment) = match (name, attachment) {
+      (Some(name), Some(attachment)) => (name, attachment),
+      _ => return Err(CallError::Invalid(Message::new("cannot_parse_upload"))),
+   };
+
+   let (data, extension) = download(&client, attachment).await?;
+   match audio_source::save(name, &guild_id, &extension, &data).await {
+      Ok(replaced) => {
+         record_uploader(name, &guild_id, command.user.id).await;
+         Ok(Message::new(if replaced { "upload_replaced" } else { "uploaded" }).with("clip", name))
+      }
+      Err(err) if err.kind() == ErrorKind::InvalidData => Err(CallError::Invalid(
+         Message::new("not_playable").with("file", &attachment.filename),
+      )),
+      Err(err) => Err(match InvalidClip::of(&err) {
+         Some(reason) => CallError::Invalid(reason.message()),
+         None => CallError::failed(Message::new("failed_save_clip").with("clip", name), err),
+      }),
+   }
+}
+
+pub async fn trim(client: Client, command: &CommandInteraction) -> CallResult {
+   let guild_id = match command.guild_id {
+      Some(guild_id) => guild_id,
+      None => return Err(CallError::Invalid(Message::new("trim_outside_server"))),
+   };
+
+   let mut name = None;
+   let mut source = None;
+   let mut attachment = None;
+   let mut start = None;
+   let mut end = None;
+   let mut strip_silence = false;
+   for option in &command.data.options {
+      match (option.name.as_str(), &option.value) {
+         ("name", CommandDataOptionValue::String(value)) => name = Some(value.as_str()),
+         ("clip", CommandDataOptionValue::String(value)) => source = Some(value.as_str()),
+         ("file", CommandDataOptionValue::Attachment(id)) => attachment = command.data.resolved.attachments.get(id),
+         ("start", CommandDataOptionValue::Number(value)) => start = Some(*value),
+         ("end", CommandDataOptionValue::Number(value)) => end = Some(*value),
+         ("strip_silence", CommandDataOptionValue::Boolean(value)) => strip_silence = *value,
+         _ => (),
+      }
+   }
+   let name = match name {
+      Some(name) => name,
+      None => return Err(CallError::Invalid(Message::new("cannot_parse_clip_name"))),
+   };
+   if start.is_none() && end.is_none() && !strip_silence {
+      return Err(CallError::Invalid(Message::new("nothing_to_trim")));
+   }
+
+   // trim the attachment if there is one, otherwise the named clip, which defaults to the one being written
+   let (data, extension, source) = match attachment {
+      Some(attachment) => {
+         let (data, extension) = download(&client, attachment).await?;
+         (data, extension, attachment.filename.as_str())
+      }
+      None => {
+         let source = source.unwrap_or(name);
+         let (data, extension) = audio_source::read(source, &guild_id)
+            .await
+            .map_err(|err| CallError::for_clip(source, Message::new("failed_read_clip").with("clip", source), err))?;
+         (data, extension, source)
+      }
+   };
+
+   // decoding and encoding a whole clip takes a while, so it is done off the runtime
+   let trimmed = task::spawn_blocking(move || {
+      let pcm = Pcm::decode(data, &extension)?.trim(start, end)?;
+      let pcm = if strip_silence { pcm.strip_silence() } else { pcm };
+      Ok((pcm.frames() > 0).then(|| (pcm.duration_secs(), pcm.to_wav())))
+   })
+   .await
+   .unwrap_or_else(|err| Err(Error::other(err)));
+   match trimmed {
+      Ok(None) => Ok(Message::new("only_silence").with("clip", source)),
+      Ok(Some((seconds, wav))) => match audio_source::save(name, &guild_id, "wav", &wav).await {
+         Ok(replaced) => {
+            record_uploader(name, &guild_id, command.user.id).await;
+            Ok(Message::new(if replaced { "trim_replaced" } else { "trim_created" })
+               .with("clip", name)
+               .with("seconds", format!("{seconds:.2}"))
+               .with("source", source))
+         }
+         Err(err) => Err(match InvalidClip::of(&err) {
+            Some(reason) => CallError::Invalid(reason.message()),
+            None => CallError::failed(Message::new("failed_save_clip").with("clip", name), err),
+         }),
+      },
+      Err(err) if err.kind() == ErrorKind::InvalidData => {
+         Err(CallError::Invalid(Message::new("not_playable").with("file", source)))
+      }
+      Err(err) => Err(match InvalidTrim::of(&err) {
+         Some(reason) => CallError::Invalid(reason.message()),
+         None => CallError::failed(Message::new("failed_trim").with("clip", source), err),
+      }),
+   }
+}
+
+pub async fn token(ctx: &Context, command: &CommandInteraction) -> CallResult {
+   let revoke = command
+      .data
+      .options
+      .iter()
+      .any(|option| option.name == "revoke" && option.value == CommandDataOptionValue::Boolean(true));
+   let tokens = api_tokens::get(ctx).await;
+
+   if revoke {
+      match tokens.revoke(command.user.id).await {
+         Ok(true) => Ok(Message::new("token_revoked")),
+         Ok(false) => Ok(Message::new("no_token")),
+         Err(err) => Err(CallError::failed(Message::new("failed_revoke_token"), err)),
+      }
+   } else {
+      match tokens.issue(command.user.id).await {
+         Ok(token) => Ok(Message::new("token_issued").with("token", token)),
+         Err(err) => Err(CallError::failed(Message::new("failed_create_token"), err)),
+      }
+   }
+}
+
+/// Downloads an uploaded clip along with the extension that tells its format.
+async fn download(client: &Client, attachment: &Attachment) -> Result<(Vec<u8>, String), CallError> {
+   if attachment.size > MAX_UPLOAD_BYTES {
+      return Err(CallError::Invalid(
+         Message::new("upload_too_large").with("mib", MAX_UPLOAD_BYTES / 1024 / 1024),
+      ));
+   }
+   let extension = match attachment.filename.rsplit_once('.') {
+      Some((_, extension)) => extension.to_lowercase(),
+      None => {
+         return Err(CallError::Invalid(
+            Message::new("no_extension").with("file", &attachment.filename),
+         ))
+      }
+   };
+
+   let response = client
+      .get(&attachment.url)
+      .send()
+      .await
+      .and_then(|res| res.error_for_status());
+   match response {
+      Ok(response) => match response.bytes().await {
+         Ok(data) => Ok((data.to_vec(), extension)),
+         Err(err) => Err(CallError::failed(Message::new("failed_download"), err)),
+      },
+      Err(err) => Err(CallError::failed(Message::new("failed_download"), err)),
+   }
+}
+
+/// The catalog entry naming what started a play.
+fn source_key(source: PlaySource) -> &'static str {
+   match source {
+      PlaySource::Command => "source_command",
+      PlaySource::Http => "source_http",
+      PlaySource::Entrance => "source_entrance",
+      PlaySource::Exit => "source_exit",
+      PlaySource::Summon => "source_summon",
+   }
+}
+
+fn origin(command: &CommandInteraction) -> Origin {
+   Origin::new(PlaySource::Command, command.user.id)
+}
+
+async fn record_uploader(name: &str, guild_id: &GuildId, user_id: UserId) {
+   if let Err(err) = clip_index::record_upload(name, guild_id, user_id).await {
+      error!("Could not record the uploader of {}: {:?}", name, err);
+   }
+}
+
+async fn check_rate_limit(ctx: &Context, user_id: UserId, connection: &ConnectionData) -> Result<(), Duration> {
+   let config = guild_config::get(ctx).await.get(connection.guild).await;
+   rate_limit::get(ctx)
+      .await
+      .check(connection.guild, user_id, &config.rate_limit)
+}
+
+/// Splits a comma separated list of tags, where `-` alone stands for no tags.
+fn parse_tags(tags: &str) -> Vec<String> {
+   let mut tags = tags
+      .split(',')
+      .map(|tag| tag.trim().to_lowercase())
+      .filter(|tag| !tag.is_empty() && tag != "-")
+      .collect::<Vec<_>>();
+   tags.sort();
+   tags.dedup();
+   tags
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn test_tags_parsed() {
+      assert_eq!(parse_tags(" Funny, loud,,funny "), ["funny", "loud"]);
+      assert!(parse_tags("-").is_empty());
    }
 }
@@ -0,0 +1,197 @@
+use rocket::{
+   http::Status,
+   request::{FromRequest, Outcome},
+   Request,
+};
+use serde::{Deserialize, Serialize};
+use serenity::{client::Context, model::id::UserId, prelude::RwLock, prelude::TypeMapKey};
+use sha2::{Digest, Sha256};
+use std::{
+   collections::{BTreeMap, HashMap},
+   fs,
+   io::{Error, ErrorKind},
+   path::{Path, PathBuf},
+   sync::Arc,
+};
+
+use crate::WebContext;
+
+const TOKEN_BYTES: usize = 32;
+
+/// The cookie the dashboard keeps a user's token in once they log in with it.
+pub const TOKEN_COOKIE: &str = "token";
+
+#[derive(Default, Deserialize, Serialize)]
+struct TokenFile {
+   /// SHA-256 hashes of each user's token, keyed by user ID
+   tokens: BTreeMap<String, String>,
+}
+
+/// The API tokens users have issued themselves for the web server, one per user.
+///
+/// Only hashes of the tokens are kept, in `api_tokens.toml` at the root of the audio file directory.
+pub struct ApiTokens {
+   path: PathBuf,
+   hashes: RwLock<HashMap<UserId, String>>,
+}
+
+impl TypeMapKey for ApiTokens {
+   type Value = Arc<ApiTokens>;
+}
+
+impl ApiTokens {
+   pub fn load(audio_file_dir: &Path) -> Result<Self, Error> {
+      Self::load_from(audio_file_dir.join("api_tokens.toml"))
+   }
+
+   fn load_from(path: PathBuf) -> Result<Self, Error> {
+      let file = match fs::read_to_string(&path) {
+         Ok(content) => toml::from_str(&content).map_err(|err| Error::new(ErrorKind::InvalidData, err))?,
+         Err(err) if err.kind() == ErrorKind::NotFound => TokenFile::default(),
+         Err(err) => return Err(err),
+      };
+      let hashes = file
+         .tokens
+         .into_iter()
+         .map(|(user_id, hash)| {
+            user_id
+               .parse()
+               .map(|id| (UserId::new(id), hash))
+               .map_err(|_| Error::new(ErrorKind::InvalidData, format!("{user_id} is not a user ID")))
+         })
+         .collect::<Result<_, _>>()?;
+      Ok(Self {
+         path,
+         hashes: RwLock::new(hashes),
+      })
+   }
+
+   /// Creates a new token for the user, replacing any they already had.
+   pub async fn issue(&self, user_id: UserId) -> Result<String, Error> {
+      let token = rand::random::<[u8; TOKEN_BYTES]>()
+         .iter()
+         .map(|byte| format!("{byte:02x}"))
+         .collect::<String>();
+      let mut hashes = self.hashes.write().await;
+      hashes.insert(user_id, hash(&token));
+      self.write(&hashes)?;
+      Ok(token)
+   }
+
+   /// Removes the user's token, returning whether they had one.
+   pub async fn revoke(&self, user_id: UserId) -> Result<bool, Error> {
+      let mut hashes = self.hashes.write().await;
+      if hashes.remove(&user_id).is_none() {
+         return Ok(false);
+      }
+      self.write(&hashes)?;
+      Ok(true)
+   }
+
+   /// Finds the user a token was issued to.
+   pub async fn owner(&self, token: &str) -> Option<UserId> {
+      let hash = hash(token);
+      self
+         .hashes
+         .read()
+         .await
+         .iter()
+         .find_map(|(user_id, stored)| (*stored == hash).then_some(*user_id))
+   }
+
+   fn write(&self, hashes: &HashMap<UserId, String>) -> Result<(), Error> {
+      let file = TokenFile {
+         tokens: hashes
+            .iter()
+            .map(|(user_id, hash)| (user_id.to_string(), hash.clone()))
+            .collect(),
+      };
+      let content = toml::to_string(&file).map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+      fs::write(&self.path, content)
+   }
+}
+
+pub async fn get(ctx: &Context) -> Arc<ApiTokens> {
+   ctx.data
+      .read()
+      .await
+      .get::<ApiTokens>()
+      .cloned()
+      .expect("API tokens should have been placed during initialization")
+}
+
+fn hash(token: &str) -> String {
+   Sha256::digest(token.as_bytes())
+      .iter()
+      .map(|byte| format!("{byte:02x}"))
+      .collect()
+}
+
+/// The user a web request acts for, identified by the `Authorization: Bearer <token>` header or, from the dashboard,
+/// the token cookie.
+pub struct ApiUser(pub UserId);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ApiUser {
+   type Error = ();
+
+   async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+      let token = match request
+         .headers()
+         .get_one("Authorization")
+         .and_then(|header| header.strip_prefix("Bearer "))
+         .or_else(|| request.cookies().get(TOKEN_COOKIE).map(|cookie| cookie.value()))
+      {
+         Some(token) => token.trim(),
+         None => return Outcome::Error((Status::Unauthorized, ())),
+      };
+      let tokens = match request.rocket().state::<WebContext>() {
+         Some(ctx) => &ctx.tokens,
+         None => return Outcome::Error((Status::InternalServerError, ())),
+      };
+      match tokens.owner(token).await {
+         Some(user_id) => Outcome::Success(ApiUser(user_id)),
+         None => Outcome::Error((Status::Unauthorized, ())),
+      }
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+   use tempfile::tempdir;
+
+   #[tokio::test]
+   async fn test_issued_token_identifies_owner() {
+      let dir = tempdir().unwrap();
+      let path = dir.path().join("api_tokens.toml");
+      let tokens = ApiTokens::load_from(path.clone()).unwrap();
+      let user_id = UserId::new(42);
+
+      let token = tokens.issue(user_id).await.unwrap();
+      assert_eq!(token.len(), TOKEN_BYTES * 2);
+      assert_eq!(tokens.owner(&token).await, Some(user_id));
+      assert_eq!(tokens.owner("not a token").await, None);
+
+      let stored = fs::read_to_string(&path).unwrap();
+      assert!(!stored.contains(&token));
+      assert!(stored.contains(&hash(&token)));
+      assert_eq!(ApiTokens::load_from(path).unwrap().owner(&token).await, Some(user_id));
+   }
+
+   #[tokio::test]
+   async fn test_reissue_and_revoke_invalidate_old_tokens() {
+      let dir = tempdir().unwrap();
+      let tokens = ApiTokens::load_from(dir.path().join("api_tokens.toml")).unwrap();
+      let user_id = UserId::new(42);
+
+      let first = tokens.issue(user_id).await.unwrap();
+      let second = tokens.issue(user_id).await.unwrap();
+      assert_eq!(tokens.owner(&first).await, None);
+      assert_eq!(tokens.owner(&second).await, Some(user_id));
+
+      assert!(tokens.revoke(user_id).await.unwrap());
+      assert_eq!(tokens.owner(&second).await, None);
+      assert!(!tokens.revoke(user_id).await.unwrap());
+   }
+}
@@ -1,26 +1,283 @@
-use serenity::model::id::GuildId;
-use songbird::input::{File as AudioFile, Input};
+use serde::Serialize;
+use serenity::model::id::{GuildId, UserId};
+use songbird::input::{
+   codecs::{CODEC_REGISTRY, PROBE},
+   File as AudioFile, Input,
+};
 use std::{
-   env,
-   io::{Error, ErrorKind},
-   path::{Component, PathBuf},
+   fmt, fs,
+   io::{Cursor, Error, ErrorKind},
+   path::{Component, Path, PathBuf},
+   sync::RwLock,
+   time::UNIX_EPOCH,
+};
+use symphonia::core::{
+   codecs::{DecoderOptions, CODEC_TYPE_NULL},
+   formats::FormatOptions,
+   io::MediaSourceStream,
+   meta::MetadataOptions,
+   probe::Hint,
+};
+use tokio::runtime::Handle;
+
+use super::{
+   clip_index::{self, AudioDetails},
+   clip_metadata, library,
 };
+use crate::locale::Message;
 
+// Set from the config at startup, since guild files are found from many places that have no context to read it from
+static FILE_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Audio formats clips may be stored in, in the order they are preferred when a name matches several files.
+pub const EXTENSIONS: [&str; 5] = ["mp3", "ogg", "opus", "wav", "flac"];
+
+/// Opens a clip for playback, failing with `NotFound` if the guild has no clip by that name.
 pub async fn file(name: &str, guild_id: &GuildId) -> Result<Input, Error> {
-   get_path(name, guild_id).await.map(|path| AudioFile::new(path).into())
+   let path = get_path(name, guild_id).await?;
+   // the file is only read once the track starts, too late to report that it is missing
+   if !path.is_file() {
+      return Err(Error::new(ErrorKind::NotFound, format!("No audio file named {name}")));
+   }
+   Ok(AudioFile::new(path).into())
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct ClipInfo {
+   pub name: String,
+   /// File extension of the clip's audio format
+   pub format: String,
+   /// File size in bytes
+   pub size: u64,
+   /// Last modification time in seconds since the Unix epoch
+   pub modified: u64,
+   /// Tags from the clip's metadata
+   pub tags: Vec<String>,
+   /// When the clip was uploaded or first noticed, in seconds since the Unix epoch
+   pub added: u64,
+   pub uploader: Option<UserId>,
+   #[serde(flatten)]
+   pub details: AudioDetails,
+}
+
+/// Lists the guild's clips, from the library when it knows them.
+pub async fn clips(guild_id: &GuildId) -> Result<Vec<ClipInfo>, Error> {
+   if let Some(clips) = library::cached(guild_id) {
+      return Ok(clips);
+   }
+   let guild = *guild_id;
+   let clips = unblock(move || scan(&guild)).await?;
+   library::store(guild_id, clips.clone());
+   Ok(clips)
+}
+
+/// Runs file work on the runtime's blocking threads, or right away outside of a runtime, as in tests.
+async fn unblock<T: Send + 'static, F: FnOnce() -> Result<T, Error> + Send + 'static>(work: F) -> Result<T, Error> {
+   match Handle::try_current() {
+      Ok(runtime) => runtime
+         .spawn_blocking(work)
+         .await
+         .unwrap_or_else(|err| Err(Error::other(err))),
+      Err(_) => work(),
+   }
+}
+
+/// Lists and indexes the clips in the guild's folder, which reads and may write files.
+fn scan(guild_id: &GuildId) -> Result<Vec<ClipInfo>, Error> {
+   let mut clips = fs::read_dir(guild_dir(guild_id))?
+      .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+      .filter_map(|path| {
+         let format = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .filter(|ext| EXTENSIONS.contains(ext))?
+            .to_owned();
+         let name = path.file_stem().and_then(|stem| stem.to_str())?.to_owned();
+         let metadata = fs::metadata(&path).ok().filter(|metadata| metadata.is_file())?;
+         let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |duration| duration.as_secs());
+         Some(ClipInfo {
+            name,
+            format,
+            size: metadata.len(),
+            modified,
+            ..Default::default()
+         })
+      })
+      .collect::<Vec<_>>();
+   // when a name exists in several formats, only the one that would be played is listed
+   clips.sort_by_key(|clip| (clip.name.clone(), EXTENSIONS.iter().position(|ext| *ext == clip.format)));
+   clips.dedup_by(|a, b| a.name == b.name);
+   clip_index::annotate(guild_id, &mut clips)?;
+   for clip in &mut clips {
+      clip.tags = clip_metadata::load(&clip.name, guild_id)
+         .map(|metadata| metadata.tags)
+         .unwrap_or_default();
+   }
+   Ok(clips)
+}
+
+pub async fn clip_names(guild_id: &GuildId) -> Result<Vec<String>, Error> {
+   clips(guild_id)
+      .await
+      .map(|clips| clips.into_iter().map(|clip| clip.name).collect())
+}
+
+/// Writes a new clip into the guild folder, returning whether an existing clip of the same name was replaced.
+///
+/// Any copies of the clip in other formats are removed so that the new one is what plays.
+pub async fn save(name: &str, guild_id: &GuildId, extension: &str, data: &[u8]) -> Result<bool, Error> {
+   if name.trim().is_empty() || name.contains(['/', '\\']) {
+      return Err(InvalidClip::Name.into());
+   }
+   if !EXTENSIONS.contains(&extension) {
+      return Err(InvalidClip::Format.into());
+   }
+   validate(data, extension)?;
+
+   let mut replaced = false;
+   for existing in EXTENSIONS.iter().filter(|existing| **existing != extension) {
+      let path = clip_path(name, guild_id, existing)?;
+      if path.is_file() {
+         fs::remove_file(path)?;
+         replaced = true;
+      }
+   }
+   let path = clip_path(name, guild_id, extension)?;
+   replaced |= path.exists();
+   fs::write(path, data)?;
+   library::invalidate(guild_id);
+   Ok(replaced)
+}
+
+/// Why `save` refused a clip, carried as the source of an `InvalidInput` error so it can be explained to the user.
+#[derive(Debug, PartialEq)]
+pub enum InvalidClip {
+   /// The name is empty or contains slashes
+   Name,
+   /// The extension is not one of `EXTENSIONS`
+   Format,
+}
+
+impl InvalidClip {
+   /// The reason behind an error, if it was raised for one.
+   pub fn of(err: &Error) -> Option<&Self> {
+      err.get_ref()?.downcast_ref()
+   }
+
+   pub fn message(&self) -> Message {
+      match self {
+         InvalidClip::Name => Message::new("invalid_clip_name"),
+         InvalidClip::Format => Message::new("unsupported_format").with("formats", EXTENSIONS.join(", ")),
+      }
+   }
+}
+
+impl fmt::Display for InvalidClip {
+   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+      self.message().fmt(f)
+   }
+}
+
+impl std::error::Error for InvalidClip {}
+
+impl From<InvalidClip> for Error {
+   fn from(reason: InvalidClip) -> Self {
+      Error::new(ErrorKind::InvalidInput, reason)
+   }
+}
+
+/// Checks that the data is a supported audio stream by decoding its first packet.
+pub fn validate(data: &[u8], extension: &str) -> Result<(), Error> {
+   let invalid = |err| Error::new(ErrorKind::InvalidData, err);
+   let stream = MediaSourceStream::new(Box::new(Cursor::new(data.to_vec())), Default::default());
+   let mut hint = Hint::new();
+   hint.with_extension(extension);
+
+   // use songbird's registries so that anything accepted here is also playable
+   let mut format = PROBE
+      .format(&hint, stream, &FormatOptions::default(), &MetadataOptions::default())
+      .map_err(invalid)?
+      .format;
+   let track = format
+      .default_track()
+      .filter(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+      .ok_or_else(|| Error::new(ErrorKind::InvalidData, "No audio track found"))?;
+   let track_id = track.id;
+   let mut decoder = CODEC_REGISTRY
+      .make(&track.codec_params, &DecoderOptions::default())
+      .map_err(invalid)?;
+
+   loop {
+      let packet = format.next_packet().map_err(invalid)?;
+      if packet.track_id() == track_id {
+         return decoder.decode(&packet).map(|_| ()).map_err(invalid);
+      }
+   }
+}
+
+/// Sets the directory holding each guild's folder of clips.
+pub fn set_file_dir(dir: PathBuf) {
+   *FILE_DIR.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(dir);
+}
+
+pub fn guild_dir(guild_id: &GuildId) -> PathBuf {
+   FILE_DIR
+      .read()
+      .unwrap_or_else(|poisoned| poisoned.into_inner())
+      .as_ref()
+      .expect("Audio file directory should have been set during initialization")
+      .join(guild_id.get().to_string())
 }
 
 async fn get_path(name: &str, guild_id: &GuildId) -> Result<PathBuf, Error> {
-   let file_dir = env::var("AUDIO_FILE_DIR").expect("Audio file directory must be in the environment!");
-   let path: PathBuf = [
-      file_dir,
-      Into::<u64>::into(*guild_id).to_string(),
-      name.to_lowercase() + ".mp3",
-   ]
-   .iter()
-   .collect();
-
-   if path.components().any(|component| component == Component::ParentDir) {
+   let lowercase = name.to_lowercase();
+   if let Some(clip) = library::cached(guild_id).and_then(|clips| clips.into_iter().find(|clip| clip.name == lowercase))
+   {
+      return clip_path(name, guild_id, &clip.format);
+   }
+
+   let paths = EXTENSIONS
+      .iter()
+      .map(|extension| clip_path(name, guild_id, extension))
+      .collect::<Result<Vec<_>, _>>()?;
+
+   // a missing clip resolves to the preferred format, which fails once it is opened
+   Ok(paths.iter().find(|path| path.is_file()).unwrap_or(&paths[0]).to_owned())
+}
+
+/// Reads a clip's file along with the extension that tells its format.
+pub async fn read(name: &str, guild_id: &GuildId) -> Result<(Vec<u8>, String), Error> {
+   let path = get_path(name, guild_id).await?;
+   let extension = path
+      .extension()
+      .and_then(|extension| extension.to_str())
+      .unwrap_or_default()
+      .to_owned();
+   Ok((fs::read(path)?, extension))
+}
+
+pub async fn exists(name: &str, guild_id: &GuildId) -> Result<bool, Error> {
+   get_path(name, guild_id).await.map(|path| path.is_file())
+}
+
+/// Resolves a file belonging to the named clip, e.g. the audio itself or its metadata sidecar.
+///
+/// The file must be directly inside the guild's folder, so names that are absolute or contain path separators are
+/// refused.
+pub fn clip_path(name: &str, guild_id: &GuildId, extension: &str) -> Result<PathBuf, Error> {
+   let dir = guild_dir(guild_id);
+   let file_name = name.to_lowercase() + "." + extension;
+   let path = dir.join(&file_name);
+
+   if Path::new(&file_name).is_absolute()
+      || file_name.contains(['/', '\\'])
+      || path.components().any(|component| component == Component::ParentDir)
+      || path.parent() != Some(dir.as_path())
+   {
       return Err(Error::new(
          ErrorKind::PermissionDenied,
          "Attempt to traverse directory hierarchy",
@@ -31,24 +288,35 @@ async fn get_path(name: &str, guild_id: &GuildId) -> Result<PathBuf, Error> {
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
    use super::*;
    use futures::executor::block_on;
    use std::{
       fs::{self, File},
       io::{Error, ErrorKind, Read, Write},
+      sync::{Mutex, MutexGuard},
    };
    use tempfile::{tempdir, TempDir};
 
+   // Tests share the audio file directory, so they must not run against each other's
+   static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+   pub(crate) fn lock_env() -> MutexGuard<'static, ()> {
+      ENV_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+   }
+
    #[test]
-   #[should_panic(expected = "Audio file directory must be in the environment!")]
+   #[should_panic(expected = "Audio file directory should have been set during initialization")]
    #[allow(unused_must_use)]
    fn test_path_requires_dir() {
+      let _lock = lock_env();
+      *FILE_DIR.write().unwrap() = None;
       block_on(get_path("some_clip", &GuildId::new(1)));
    }
 
    #[test]
    fn test_guild_clip_retrieved() -> Result<(), Error> {
+      let _lock = lock_env();
       let dir = setup_temp_directories()?;
 
       let mut file = File::open(block_on(get_path("clip", &GuildId::new(1)))?)?;
@@ -69,6 +337,7 @@ mod tests {
 
    #[test]
    fn test_relative_path_traversal_disallowed() -> Result<(), Error> {
+      let _lock = lock_env();
       let dir = setup_temp_directories()?;
 
       match block_on(get_path("../2/clip", &GuildId::new(1))) {
@@ -85,7 +354,155 @@ mod tests {
       Ok(())
    }
 
-   fn setup_temp_directories() -> Result<TempDir, Error> {
+   #[test]
+   fn test_names_outside_guild_dir_disallowed() -> Result<(), Error> {
+      let _lock = lock_env();
+      let dir = setup_temp_directories()?;
+      let guild_id = GuildId::new(1);
+      let outside = dir.path().join("2").join("clip");
+
+      for name in [outside.to_str().unwrap(), "2/clip", "..\\2\\clip", "/etc/passwd"] {
+         let err = block_on(read(name, &guild_id)).err().map(|err| err.kind());
+         assert_eq!(err, Some(ErrorKind::PermissionDenied), "{name} was allowed");
+      }
+      assert_eq!(
+         clip_path("clip", &guild_id, "mp3")?,
+         dir.path().join("1").join("clip.mp3")
+      );
+
+      dir.close()?;
+      Ok(())
+   }
+
+   #[test]
+   fn test_missing_file_not_found() -> Result<(), Error> {
+      let _lock = lock_env();
+      let dir = setup_temp_directories()?;
+
+      assert!(block_on(file("clip", &GuildId::new(1))).is_ok());
+      let err = block_on(file("missing", &GuildId::new(1))).err().map(|err| err.kind());
+      assert_eq!(err, Some(ErrorKind::NotFound));
+
+      dir.close()?;
+      Ok(())
+   }
+
+   #[test]
+   fn test_clip_names_listed() -> Result<(), Error> {
+      let _lock = lock_env();
+      let dir = setup_temp_directories()?;
+      File::create(dir.path().join("1").join(".role_id"))?;
+      File::create(dir.path().join("1").join("notes.txt"))?;
+
+      assert_eq!(block_on(clip_names(&GuildId::new(1)))?, vec!["another_clip", "clip"]);
+
+      let clips = block_on(clips(&GuildId::new(2)))?;
+      assert_eq!(clips.len(), 2);
+      assert_eq!(clips[1].name, "clip");
+      assert_eq!(clips[1].size, "second guild clip".len() as u64);
+      assert!(clips[1].modified > 0);
+
+      dir.close()?;
+      Ok(())
+   }
+
+   #[test]
+   fn test_non_audio_rejected() {
+      let err = validate(b"definitely not an mp3", "mp3").unwrap_err();
+
+      assert_eq!(err.kind(), ErrorKind::InvalidData);
+   }
+
+   #[test]
+   fn test_wav_accepted() -> Result<(), Error> {
+      validate(&silent_wav(), "wav")
+   }
+
+   #[test]
+   fn test_preferred_format_resolved() -> Result<(), Error> {
+      let _lock = lock_env();
+      let dir = setup_temp_directories()?;
+      let guild = dir.path().join("1");
+      File::create(guild.join("clip.wav"))?;
+      File::create(guild.join("wave.flac"))?;
+      File::create(guild.join("wave.wav"))?;
+
+      assert_eq!(block_on(get_path("clip", &GuildId::new(1)))?, guild.join("clip.mp3"));
+      assert_eq!(block_on(get_path("wave", &GuildId::new(1)))?, guild.join("wave.wav"));
+      assert_eq!(
+         block_on(get_path("missing", &GuildId::new(1)))?,
+         guild.join("missing.mp3")
+      );
+
+      let clips = block_on(clips(&GuildId::new(1)))?;
+      let formats = clips
+         .iter()
+         .map(|clip| (clip.name.as_str(), clip.format.as_str()))
+         .collect::<Vec<_>>();
+      assert_eq!(formats, vec![("another_clip", "mp3"), ("clip", "mp3"), ("wave", "wav")]);
+
+      dir.close()?;
+      Ok(())
+   }
+
+   #[test]
+   fn test_save_replaces_other_formats() -> Result<(), Error> {
+      let _lock = lock_env();
+      let dir = setup_temp_directories()?;
+      let guild = dir.path().join("1");
+
+      assert!(block_on(save("clip", &GuildId::new(1), "wav", &silent_wav()))?);
+      assert!(!guild.join("clip.mp3").exists());
+      assert!(guild.join("clip.wav").is_file());
+      assert!(!block_on(save("new", &GuildId::new(1), "wav", &silent_wav()))?);
+
+      dir.close()?;
+      Ok(())
+   }
+
+   /// A short mono 16-bit PCM WAV file of silence.
+   pub(crate) fn silent_wav() -> Vec<u8> {
+      wav(8000, &[0; 800])
+   }
+
+   /// Builds a mono 16-bit PCM WAV file holding the samples.
+   pub(crate) fn wav(sample_rate: u32, samples: &[i16]) -> Vec<u8> {
+      let data_len = samples.len() as u32 * 2;
+      let mut wav = Vec::new();
+      wav.extend_from_slice(b"RIFF");
+      wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+      wav.extend_from_slice(b"WAVEfmt ");
+      wav.extend_from_slice(&16u32.to_le_bytes());
+      wav.extend_from_slice(&1u16.to_le_bytes());
+      wav.extend_from_slice(&1u16.to_le_bytes());
+      wav.extend_from_slice(&sample_rate.to_le_bytes());
+      wav.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+      wav.extend_from_slice(&2u16.to_le_bytes());
+      wav.extend_from_slice(&16u16.to_le_bytes());
+      wav.extend_from_slice(b"data");
+      wav.extend_from_slice(&data_len.to_le_bytes());
+      samples
+         .iter()
+         .for_each(|sample| wav.extend_from_slice(&sample.to_le_bytes()));
+      wav
+   }
+
+   #[test]
+   fn test_save_rejects_nested_names() -> Result<(), Error> {
+      let _lock = lock_env();
+      let dir = setup_temp_directories()?;
+
+      let err = block_on(save("nested/clip", &GuildId::new(1), "mp3", b"")).unwrap_err();
+      assert_eq!(err.kind(), ErrorKind::InvalidInput);
+      assert_eq!(InvalidClip::of(&err), Some(&InvalidClip::Name));
+      let err = block_on(save("clip", &GuildId::new(1), "aac", b"")).unwrap_err();
+      assert_eq!(err.to_string(), "Clips must be one of: mp3, ogg, opus, wav, flac");
+
+      dir.close()?;
+      Ok(())
+   }
+
+   pub(crate) fn setup_temp_directories() -> Result<TempDir, Error> {
       let dir = tempdir()?;
       let first_guild = dir.path().join("1");
       let second_guild = dir.path().join("2");
@@ -104,7 +521,7 @@ mod tests {
       let mut another_second_guild_file = File::create(second_guild.join("another_clip.mp3"))?;
       another_second_guild_file.write_all(b"another second guild file")?;
 
-      env::set_var("AUDIO_FILE_DIR", dir.path().as_os_str());
+      set_file_dir(dir.path().to_owned());
       Ok(dir)
    }
 }
@@ -0,0 +1,544 @@
+use ebur128::{EbuR128, Mode};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use serenity::model::id::{GuildId, UserId};
+use std::{
+   collections::{BTreeMap, BTreeSet},
+   fs::{self, File},
+   io::{Error, ErrorKind},
+   path::{Path, PathBuf},
+   sync::Mutex,
+   time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::{runtime::Handle, task};
+
+use super::{
+   audio_source::{self, ClipInfo},
+   decode, library,
+};
+use crate::locale::Message;
+
+const FILE_NAME: &str = "index.toml";
+// Raised whenever entries gain details, so that clips indexed before then are probed again
+const INDEX_VERSION: u32 = 2;
+// The most a quiet clip is boosted to reach the loudness target, so near-silence isn't turned into noise
+const MAX_LOUDNESS_BOOST_DB: f32 = 12.0;
+
+// Index files are read, updated and written back whole, so changes to them must not interleave
+static INDEX_LOCK: Mutex<()> = Mutex::new(());
+// Guilds whose clips are being probed in the background, so each guild only has one task doing it
+static PROBING: Mutex<BTreeSet<GuildId>> = Mutex::new(BTreeSet::new());
+
+/// What is known about a clip beyond its name, as stored in the guild's `index.toml`.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+struct IndexEntry {
+   format: String,
+   size: u64,
+   modified: u64,
+   /// When the clip was uploaded or first noticed, in seconds since the Unix epoch
+   added: u64,
+   uploader: Option<UserId>,
+   /// Whether the clip still has to be probed for its details
+   #[serde(skip_serializing_if = "std::ops::Not::not")]
+   pending: bool,
+   #[serde(flatten)]
+   details: AudioDetails,
+}
+
+/// The properties of a clip's audio stream, any of which a format may not reveal.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct AudioDetails {
+   pub duration_ms: Option<u64>,
+   pub sample_rate: Option<u32>,
+   pub channels: Option<u32>,
+   /// Integrated loudness per EBU R128
+   pub loudness_lufs: Option<f32>,
+   /// The loudest sample of any channel, in dB relative to full scale
+   pub peak_dbfs: Option<f32>,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default)]
+struct ClipIndex {
+   version: u32,
+   clips: BTreeMap<String, IndexEntry>,
+}
+
+/// Brings the guild's index up to date with the listed clips and fills in each clip's details from it.
+///
+/// Clips whose format, size or modification time changed since they were last indexed are probed again in the
+/// background, since that decodes them whole. Until then their details are left unknown.
+pub fn annotate(guild_id: &GuildId, clips: &mut [ClipInfo]) -> Result<(), Error> {
+   if !index_clips(guild_id, clips)? {
+      return Ok(());
+   }
+   match Handle::try_current() {
+      Ok(runtime) => {
+         if PROBING
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(*guild_id)
+         {
+            runtime.spawn(probe_in_background(*guild_id));
+         }
+         Ok(())
+      }
+      // outside of a runtime, as in tests, there is nothing to hold up by probing right away
+      Err(_) => {
+         for (name, entry) in pending(guild_id)? {
+            let details = probe_entry(guild_id, &name, &entry);
+            store_details(guild_id, &name, &entry, details)?;
+         }
+         index_clips(guild_id, clips).map(|_| ())
+      }
+   }
+}
+
+/// Updates the index with the listed clips and fills in their details, returning whether any clips await probing.
+fn index_clips(guild_id: &GuildId, clips: &mut [ClipInfo]) -> Result<bool, Error> {
+   let _lock = INDEX_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+   let path = path(guild_id);
+   let mut index = read(&path)?;
+   let outdated = index.version != INDEX_VERSION;
+   let mut changed = outdated || index.clips.len() != clips.len();
+
+   let mut entries = BTreeMap::new();
+   for clip in clips.iter_mut() {
+      let entry = match index.clips.remove(&clip.name) {
+         Some(entry)
+            if !outdated
+               && entry.format == clip.format
+               && entry.size == clip.size
+               && entry.modified == clip.modified =>
+         {
+            entry
+         }
+         previous => {
+            changed = true;
+            IndexEntry {
+               format: clip.format.clone(),
+               size: clip.size,
+               modified: clip.modified,
+               added: previous.as_ref().map_or_else(now, |previous| previous.added),
+               uploader: previous.and_then(|previous| previous.uploader),
+               pending: true,
+               details: AudioDetails::default(),
+            }
+         }
+      };
+      clip.added = entry.added;
+      clip.uploader = entry.uploader;
+      clip.details = entry.details.clone();
+      entries.insert(clip.name.clone(), entry);
+   }
+
+   let any_pending = entries.values().any(|entry| entry.pending);
+   if changed {
+      index.version = INDEX_VERSION;
+      index.clips = entries;
+      write(&path, &index)?;
+   }
+   Ok(any_pending)
+}
+
+/// Probes the guild's pending clips one at a time off the runtime, listing the clips again after each round so the
+/// library picks up their details.
+async fn probe_in_background(guild_id: GuildId) {
+   loop {
+      let pending = {
+         let mut probing = PROBING.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+         match pending(&guild_id) {
+            Ok(pending) if !pending.is_empty() => pending,
+            result => {
+               if let Err(err) = result {
+                  warn!("Could not read the clip index of {:?}: {:?}", guild_id, err);
+               }
+               probing.remove(&guild_id);
+               return;
+            }
+         }
+      };
+
+      for (name, entry) in pending {
+         let probed = (name.clone(), entry.clone());
+         let details = task::spawn_blocking(move || probe_entry(&guild_id, &probed.0, &probed.1))
+            .await
+            .unwrap_or_default();
+         if let Err(err) = store_details(&guild_id, &name, &entry, details) {
+            warn!("Could not index {} for {:?}: {:?}", name, guild_id, err);
+            PROBING
+               .lock()
+               .unwrap_or_else(|poisoned| poisoned.into_inner())
+               .remove(&guild_id);
+            return;
+         }
+      }
+      library::invalidate(&guild_id);
+      if let Err(err) = audio_source::clips(&guild_id).await {
+         warn!(
+            "Could not list the clips of {:?} after probing them: {:?}",
+            guild_id, err
+         );
+      }
+   }
+}
+
+fn pending(guild_id: &GuildId) -> Result<Vec<(String, IndexEntry)>, Error> {
+   let _lock = INDEX_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+   Ok(read(&path(guild_id))?
+      .clips
+      .into_iter()
+      .filter(|(_, entry)| entry.pending)
+      .collect())
+}
+
+fn probe_entry(guild_id: &GuildId, name: &str, entry: &IndexEntry) -> AudioDetails {
+   audio_source::clip_path(name, guild_id, &entry.format)
+      .and_then(|clip_path| probe(&clip_path, &entry.format))
+      .unwrap_or_else(|err| {
+         warn!("Could not probe {} for {:?}: {:?}", name, guild_id, err);
+         AudioDetails::default()
+      })
+}
+
+/// Saves the details probed from a clip, unless the clip changed again while it was being probed.
+fn store_details(guild_id: &GuildId, name: &str, probed: &IndexEntry, details: AudioDetails) -> Result<(), Error> {
+   let _lock = INDEX_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+   let path = path(guild_id);
+   let mut index = read(&path)?;
+   match index.clips.get_mut(name) {
+      Some(entry)
+         if entry.pending
+            && entry.format == probed.format
+            && entry.size == probed.size
+            && entry.modified == probed.modified =>
+      {
+         entry.pending = false;
+         entry.details = details;
+         write(&path, &index)
+      }
+      _ => Ok(()),
+   }
+}
+
+/// Records who uploaded a clip, marking it as newly added.
+pub async fn record_upload(name: &str, guild_id: &GuildId, uploader: UserId) -> Result<(), Error> {
+   // listing the clips indexes the new upload
+   audio_source::clips(guild_id).await?;
+
+   let _lock = INDEX_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+   let path = path(guild_id);
+   let mut index = read(&path)?;
+   let entry = index
+      .clips
+      .get_mut(&name.to_lowercase())
+      .ok_or_else(|| Error::new(ErrorKind::NotFound, format!("No clip named {name}")))?;
+   entry.uploader = Some(uploader);
+   entry.added = now();
+   write(&path, &index)?;
+   library::invalidate(guild_id);
+   Ok(())
+}
+
+/// Looks up a single clip's details, indexing the guild's clips first if needed.
+pub async fn clip(name: &str, guild_id: &GuildId) -> Option<ClipInfo> {
+   let name = name.to_lowercase();
+   audio_source::clips(guild_id)
+      .await
+      .ok()?
+      .into_iter()
+      .find(|clip| clip.name == name)
+}
+
+/// The gain that brings a clip to the target loudness, leaving clips of unknown loudness as they are.
+///
+/// Clips are boosted by no more than their peak allows, so normalizing never makes them clip.
+pub fn loudness_gain(clip: &ClipInfo, target_lufs: f32) -> f32 {
+   match clip.details.loudness_lufs {
+      Some(lufs) => {
+         let headroom = clip.details.peak_dbfs.map_or(f32::INFINITY, |peak| -peak);
+         let db = (target_lufs - lufs).min(MAX_LOUDNESS_BOOST_DB).min(headroom.max(0.0));
+         10f32.powf(db / 20.0)
+      }
+      None => 1.0,
+   }
+}
+
+/// Formats a duration in milliseconds the way clip lengths are shown to users.
+pub fn format_duration(duration_ms: u64) -> String {
+   let seconds = duration_ms as f64 / 1000.0;
+   if seconds < 60.0 {
+      format!("{seconds:.1}s")
+   } else {
+      format!("{}:{:02}", duration_ms / 60_000, duration_ms / 1000 % 60)
+   }
+}
+
+/// Describes a clip's length, audio format and uploader, leaving out whatever isn't known, or `None` if nothing is.
+pub fn summary(clip: &ClipInfo) -> Option<Message> {
+   let details = &clip.details;
+   let audio = details.sample_rate.map(|rate| {
+      match details.channels {
+         Some(1) => Message::new("clip_mono"),
+         Some(2) => Message::new("clip_stereo"),
+         Some(channels) => Message::new("clip_channels").with("channels", channels),
+         None => Message::new("clip_sample_rate"),
+      }
+      .with("khz", format!("{:.1}", rate as f32 / 1000.0))
+   });
+   let parts = [
+      details
+         .duration_ms
+         .map(|duration| Message::Plain(format_duration(duration))),
+      audio,
+      clip
+         .uploader
+         .map(|uploader| Message::new("clip_uploaded_by").with("user", format!("<@{uploader}>"))),
+   ]
+   .into_iter()
+   .flatten()
+   .collect::<Vec<_>>();
+   (!parts.is_empty()).then_some(Message::List(parts))
+}
+
+/// Decodes a whole clip to find its length and measure its integrated loudness.
+fn probe(path: &Path, extension: &str) -> Result<AudioDetails, Error> {
+   let mut frames = 0;
+   let mut meter = None;
+   let spec = decode::decode(Box::new(File::open(path)?), extension, |spec, samples| {
+      let channels = spec.channels.count();
+      frames += (samples.len() / channels.max(1)) as u64;
+      if meter.is_none() {
+         meter = EbuR128::new(channels as u32, spec.rate, Mode::I | Mode::SAMPLE_PEAK).ok();
+      }
+      if let Some(meter) = meter.as_mut() {
+         if meter.add_frames_f32(samples).is_err() {
+            warn!("Could not measure the loudness of {:?}", path);
+         }
+      }
+   })?;
+
+   let peak = meter.as_ref().and_then(|meter| {
+      (0..spec.channels.count() as u32)
+         .filter_map(|channel| meter.sample_peak(channel).ok())
+         .reduce(f64::max)
+   });
+   Ok(AudioDetails {
+      duration_ms: Some(frames * 1000 / u64::from(spec.rate)),
+      sample_rate: Some(spec.rate),
+      channels: Some(spec.channels.count() as u32),
+      // silence has no loudness to speak of
+      loudness_lufs: meter
+         .and_then(|meter| meter.loudness_global().ok())
+         .filter(|lufs| lufs.is_finite())
+         .map(|lufs| (lufs * 10.0).round() as f32 / 10.0),
+      peak_dbfs: peak
+         .filter(|peak| *peak > 0.0)
+         .map(|peak| (20.0 * peak.log10() * 10.0).ceil() as f32 / 10.0),
+   })
+}
+
+fn path(guild_id: &GuildId) -> PathBuf {
+   audio_source::guild_dir(guild_id).join(FILE_NAME)
+}
+
+fn read(path: &Path) -> Result<ClipIndex, Error> {
+   match fs::read_to_string(path) {
+      Ok(content) => Ok(toml::from_str(&content).unwrap_or_else(|err| {
+         // the index can always be rebuilt, apart from who uploaded what
+         warn!("Discarding unreadable clip index {:?}: {:?}", path, err);
+         ClipIndex::default()
+      })),
+      Err(err) if err.kind() == ErrorKind::NotFound => Ok(ClipIndex::default()),
+      Err(err) => Err(err),
+   }
+}
+
+fn write(path: &Path, index: &ClipIndex) -> Result<(), Error> {
+   let content = toml::to_string(index).map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+   fs::write(path, content)
+}
+
+fn now() -> u64 {
+   SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map_or(0, |duration| duration.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+   use crate::{
+      audio::audio_source::tests::{lock_env, setup_temp_directories, silent_wav, wav},
+      locale::Locale,
+   };
+   use futures::executor::block_on;
+
+   #[test]
+   fn test_clips_probed_and_indexed() -> Result<(), Error> {
+      let _lock = lock_env();
+      let dir = setup_temp_directories()?;
+      let guild_id = GuildId::new(1);
+      block_on(audio_source::save("silence", &guild_id, "wav", &silent_wav()))?;
+
+      let silence = block_on(clip("silence", &guild_id)).unwrap();
+      assert_eq!(
+         silence.details,
+         AudioDetails {
+            duration_ms: Some(100),
+            sample_rate: Some(8000),
+            channels: Some(1),
+            loudness_lufs: None,
+            peak_dbfs: None,
+         }
+      );
+      // the placeholder clips aren't real audio, but are still indexed
+      assert_eq!(
+         block_on(clip("clip", &guild_id)).unwrap().details,
+         AudioDetails::default()
+      );
+
+      let index = read(&path(&guild_id))?;
+      assert_eq!(index.clips.len(), 3);
+      assert_eq!(index.clips["silence"].details.duration_ms, Some(100));
+
+      dir.close()?;
+      Ok(())
+   }
+
+   #[test]
+   fn test_clips_probed_in_background() -> Result<(), Error> {
+      let _lock = lock_env();
+      let dir = setup_temp_directories()?;
+      let guild_id = GuildId::new(1);
+      fs::write(audio_source::clip_path("silence", &guild_id, "wav")?, silent_wav())?;
+
+      let (first, probed) = tokio::runtime::Runtime::new()?.block_on(async {
+         let first = clip("silence", &guild_id).await.unwrap().details;
+         for _ in 0..50 {
+            if !PROBING.lock().unwrap().contains(&guild_id) {
+               break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+         }
+         (first, clip("silence", &guild_id).await.unwrap().details)
+      });
+
+      // listing never waits for the clip to be decoded
+      assert_eq!(first, AudioDetails::default());
+      assert_eq!(probed.duration_ms, Some(100));
+      assert!(!read(&path(&guild_id))?.clips["silence"].pending);
+
+      dir.close()?;
+      Ok(())
+   }
+
+   #[test]
+   fn test_uploader_kept_until_removed() -> Result<(), Error> {
+      let _lock = lock_env();
+      let dir = setup_temp_directories()?;
+      let guild_id = GuildId::new(1);
+      let uploader = UserId::new(42);
+
+      block_on(audio_source::save("silence", &guild_id, "wav", &silent_wav()))?;
+      block_on(record_upload("Silence", &guild_id, uploader))?;
+      assert_eq!(block_on(clip("silence", &guild_id)).unwrap().uploader, Some(uploader));
+
+      fs::write(
+         audio_source::clip_path("silence", &guild_id, "wav")?,
+         [silent_wav(), vec![0; 16]].concat(),
+      )?;
+      assert_eq!(block_on(clip("silence", &guild_id)).unwrap().uploader, Some(uploader));
+
+      fs::remove_file(audio_source::clip_path("silence", &guild_id, "wav")?)?;
+      assert!(block_on(clip("silence", &guild_id)).is_none());
+      assert!(!read(&path(&guild_id))?.clips.contains_key("silence"));
+
+      dir.close()?;
+      Ok(())
+   }
+
+   #[test]
+   fn test_loudness_measured() -> Result<(), Error> {
+      let _lock = lock_env();
+      let dir = setup_temp_directories()?;
+      let guild_id = GuildId::new(1);
+      // a full scale 1 kHz sine on one channel measures -3 LUFS, so one at half scale is 6 dB quieter
+      let sine = (0..48000)
+         .map(|i| (0.5 * (2.0 * std::f32::consts::PI * 1000.0 * i as f32 / 48000.0).sin() * 32767.0) as i16)
+         .collect::<Vec<_>>();
+      block_on(audio_source::save("sine", &guild_id, "wav", &wav(48000, &sine)))?;
+
+      let sine = block_on(clip("sine", &guild_id)).unwrap();
+      let lufs = sine.details.loudness_lufs.unwrap();
+      assert!((lufs + 9.0).abs() < 0.5, "measured {lufs} LUFS");
+      assert_eq!(sine.details.peak_dbfs, Some(-6.0));
+      // 9 dB quieter
+      assert!((loudness_gain(&sine, -18.0) - 0.355).abs() < 0.02);
+
+      dir.close()?;
+      Ok(())
+   }
+
+   #[test]
+   fn test_loudness_boost_limited() {
+      let quiet = ClipInfo {
+         details: AudioDetails {
+            loudness_lufs: Some(-60.0),
+            ..Default::default()
+         },
+         ..Default::default()
+      };
+
+      assert_eq!(loudness_gain(&quiet, -18.0), 10f32.powf(MAX_LOUDNESS_BOOST_DB / 20.0));
+      assert_eq!(loudness_gain(&ClipInfo::default(), -18.0), 1.0);
+   }
+
+   #[test]
+   fn test_loudness_boost_limited_by_peak() {
+      let mut quiet = ClipInfo {
+         details: AudioDetails {
+            loudness_lufs: Some(-30.0),
+            peak_dbfs: Some(-3.0),
+            ..Default::default()
+         },
+         ..Default::default()
+      };
+
+      assert_eq!(loudness_gain(&quiet, -18.0), 10f32.powf(3.0 / 20.0));
+      // clips that already reach full scale can still be turned down
+      quiet.details.peak_dbfs = Some(0.0);
+      assert_eq!(loudness_gain(&quiet, -18.0), 1.0);
+      assert_eq!(loudness_gain(&quiet, -40.0), 10f32.powf(-10.0 / 20.0));
+   }
+
+   #[test]
+   fn test_summary_skips_unknowns() {
+      let clip = ClipInfo {
+         uploader: Some(UserId::new(42)),
+         details: AudioDetails {
+            duration_ms: Some(3200),
+            sample_rate: Some(44100),
+            channels: Some(2),
+            ..Default::default()
+         },
+         ..Default::default()
+      };
+
+      let summary_in = |locale| summary(&clip).unwrap().text(locale);
+      assert_eq!(summary_in(Locale::English), "3.2s, 44.1 kHz stereo, uploaded by <@42>");
+      assert_eq!(
+         summary_in(Locale::German),
+         "3.2s, 44.1 kHz Stereo, hochgeladen von <@42>"
+      );
+      assert_eq!(summary(&ClipInfo::default()), None);
+   }
+
+   #[test]
+   fn test_duration_formatted() {
+      assert_eq!(format_duration(1260), "1.3s");
+      assert_eq!(format_duration(95_000), "1:35");
+   }
+}
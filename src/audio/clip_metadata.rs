@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+use serenity::model::id::GuildId;
+use std::{
+   fs,
+   io::{Error, ErrorKind},
+};
+
+use crate::audio::{audio_source, library};
+
+const EXTENSION: &str = "meta.toml";
+
+/// Per-clip settings stored in a `<name>.meta.toml` sidecar next to the clip.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct ClipMetadata {
+   /// Multiplier applied on top of the guild volume whenever this clip plays
+   pub gain: f32,
+   /// Extra words `/search` matches the clip by
+   pub tags: Vec<String>,
+}
+
+impl Default for ClipMetadata {
+   fn default() -> Self {
+      Self {
+         gain: 1.0,
+         tags: Vec::new(),
+      }
+   }
+}
+
+pub fn load(name: &str, guild_id: &GuildId) -> Result<ClipMetadata, Error> {
+   match fs::read_to_string(audio_source::clip_path(name, guild_id, EXTENSION)?) {
+      Ok(content) => toml::from_str(&content).map_err(|err| Error::new(ErrorKind::InvalidData, err)),
+      Err(err) if err.kind() == ErrorKind::NotFound => Ok(ClipMetadata::default()),
+      Err(err) => Err(err),
+   }
+}
+
+pub async fn save(name: &str, guild_id: &GuildId, metadata: &ClipMetadata) -> Result<(), Error> {
+   if !audio_source::exists(name, guild_id).await? {
+      return Err(Error::new(ErrorKind::NotFound, format!("No clip named {name}")));
+   }
+   let content = toml::to_string(metadata).map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+   fs::write(audio_source::clip_path(name, guild_id, EXTENSION)?, content)?;
+   // the tags are listed with each clip
+   library::invalidate(guild_id);
+   Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+   use crate::audio::audio_source::tests::{lock_env, setup_temp_directories};
+   use futures::executor::block_on;
+
+   #[test]
+   fn test_metadata_round_trip() -> Result<(), Error> {
+      let _lock = lock_env();
+      let dir = setup_temp_directories()?;
+      let guild_id = GuildId::new(1);
+
+      assert_eq!(load("clip", &guild_id)?, ClipMetadata::default());
+      let metadata = ClipMetadata {
+         gain: 0.5,
+         tags: vec!["funny".to_string()],
+      };
+      block_on(save("Clip", &guild_id, &metadata))?;
+      assert_eq!(load("clip", &guild_id)?, metadata);
+      assert_eq!(load("another_clip", &guild_id)?.gain, 1.0);
+
+      dir.close()?;
+      Ok(())
+   }
+
+   #[test]
+   fn test_metadata_requires_clip() -> Result<(), Error> {
+      let _lock = lock_env();
+      let dir = setup_temp_directories()?;
+
+      let err = block_on(save("missing", &GuildId::new(1), &ClipMetadata::default())).unwrap_err();
+      assert_eq!(err.kind(), ErrorKind::NotFound);
+
+      dir.close()?;
+      Ok(())
+   }
+}
@@ -1,7 +1,7 @@
 use serenity::{
    client::{Cache, Context},
    model::{
-      application::CommandInteraction,
+      application::{CommandInteraction, ComponentInteraction},
       id::{ChannelId, GuildId},
       user::User,
    },
@@ -16,9 +16,17 @@ pub struct ConnectionData {
 
 impl ConnectionData {
    pub async fn try_from_command(ctx: &Context, command: &CommandInteraction) -> Option<Self> {
-      match command.guild_id {
-         Some(guild_id) => Self::try_from_guild_user(&ctx.cache, guild_id, &command.user),
-         None => Self::try_from_user(ctx, &command.user).await,
+      Self::try_from_interaction(ctx, command.guild_id, &command.user).await
+   }
+
+   pub async fn try_from_component(ctx: &Context, component: &ComponentInteraction) -> Option<Self> {
+      Self::try_from_interaction(ctx, component.guild_id, &component.user).await
+   }
+
+   async fn try_from_interaction(ctx: &Context, guild_id: Option<GuildId>, user: &User) -> Option<Self> {
+      match guild_id {
+         Some(guild_id) => Self::try_from_guild_user(&ctx.cache, guild_id, user),
+         None => Self::try_from_user(ctx, user).await,
       }
    }
 
@@ -0,0 +1,57 @@
+use songbird::input::codecs::{CODEC_REGISTRY, PROBE};
+use std::io::{Error, ErrorKind};
+use symphonia::core::{
+   audio::{SampleBuffer, SignalSpec},
+   codecs::{DecoderOptions, CODEC_TYPE_NULL},
+   errors::Error as SymphoniaError,
+   formats::FormatOptions,
+   io::{MediaSource, MediaSourceStream},
+   meta::MetadataOptions,
+   probe::Hint,
+};
+
+/// Decodes the whole default track, handing each packet's interleaved samples to `on_samples` as they are decoded.
+///
+/// Returns the layout of the decoded audio. Reading stops at the end of the stream, or at the first damage that
+/// can't be skipped over.
+pub fn decode<F: FnMut(&SignalSpec, &[f32])>(
+   source: Box<dyn MediaSource>, extension: &str, mut on_samples: F,
+) -> Result<SignalSpec, Error> {
+   let invalid = |err| Error::new(ErrorKind::InvalidData, err);
+   let stream = MediaSourceStream::new(source, Default::default());
+   let mut hint = Hint::new();
+   hint.with_extension(extension);
+
+   // use songbird's registries so that anything decoded here is also playable
+   let mut format = PROBE
+      .format(&hint, stream, &FormatOptions::default(), &MetadataOptions::default())
+      .map_err(invalid)?
+      .format;
+   let track = format
+      .default_track()
+      .filter(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+      .ok_or_else(|| Error::new(ErrorKind::InvalidData, "No audio track found"))?;
+   let track_id = track.id;
+   let mut decoder = CODEC_REGISTRY
+      .make(&track.codec_params, &DecoderOptions::default())
+      .map_err(invalid)?;
+
+   let mut spec = None;
+   while let Ok(packet) = format.next_packet() {
+      if packet.track_id() != track_id {
+         continue;
+      }
+      let decoded = match decoder.decode(&packet) {
+         Ok(decoded) => decoded,
+         Err(SymphoniaError::DecodeError(_)) => continue,
+         Err(_) => break,
+      };
+      let decoded_spec = *decoded.spec();
+      let mut samples = SampleBuffer::<f32>::new(decoded.capacity() as u64, decoded_spec);
+      samples.copy_interleaved_ref(decoded);
+      on_samples(&decoded_spec, samples.samples());
+      spec.get_or_insert(decoded_spec);
+   }
+
+   spec.ok_or_else(|| Error::new(ErrorKind::InvalidData, "No audio could be decoded"))
+}
@@ -0,0 +1,146 @@
+use log::{error, info};
+use serenity::model::{id::GuildId, user::User};
+use std::{fs, io::Error};
+
+use crate::audio::audio_source;
+
+// Exit clips are named like entrances with this suffix, e.g. `<user_id>.exit.mp3`
+const EXIT_SUFFIX: &str = ".exit";
+
+/// Resolves the clip to play for a user, preferring one named by their ID since usernames can change.
+pub async fn clip_name(guild_id: &GuildId, user: &User) -> String {
+   migrate(guild_id, user).await;
+   let id_name = user.id.to_string();
+   match audio_source::exists(&id_name, guild_id).await {
+      Ok(true) => id_name,
+      _ => user.name.clone(),
+   }
+}
+
+/// Resolves the clip to play when a user leaves, by ID and then username, if they have one.
+pub async fn exit_clip_name(guild_id: &GuildId, user: &User) -> Option<String> {
+   migrate(guild_id, user).await;
+   for name in [user.id.to_string() + EXIT_SUFFIX, user.name.clone() + EXIT_SUFFIX] {
+      if let Ok(true) = audio_source::exists(&name, guild_id).await {
+         return Some(name);
+      }
+   }
+   None
+}
+
+/// Whether a clip belongs to a member rather than the guild, i.e. it is named by a user ID or is an exit clip.
+pub fn is_member_clip(name: &str) -> bool {
+   name.ends_with(EXIT_SUFFIX) || (!name.is_empty() && name.bytes().all(|byte| byte.is_ascii_digit()))
+}
+
+/// Renames the user's entrance and exit clips from their username to their ID, so they keep working if the username
+/// changes. Clips that already have an ID-named counterpart are left alone.
+async fn migrate(guild_id: &GuildId, user: &User) {
+   if let Err(err) = migrate_user(guild_id, user).await {
+      error!(
+         "Could not migrate entrance for {} in {}: {:?}",
+         user.name, guild_id, err
+      );
+   }
+}
+
+async fn migrate_user(guild_id: &GuildId, user: &User) -> Result<bool, Error> {
+   let id_name = user.id.to_string();
+   let entrance = migrate_clip(guild_id, &user.name, &id_name).await?;
+   let exit = migrate_clip(guild_id, &(user.name.clone() + EXIT_SUFFIX), &(id_name + EXIT_SUFFIX)).await?;
+   Ok(entrance || exit)
+}
+
+async fn migrate_clip(guild_id: &GuildId, from_name: &str, to_name: &str) -> Result<bool, Error> {
+   if !audio_source::exists(from_name, guild_id).await? || audio_source::exists(to_name, guild_id).await? {
+      return Ok(false);
+   }
+
+   for extension in audio_source::EXTENSIONS.iter().chain(["meta.toml"].iter()) {
+      let from = audio_source::clip_path(from_name, guild_id, extension)?;
+      if from.is_file() {
+         let to = audio_source::clip_path(to_name, guild_id, extension)?;
+         info!("Renaming {} to {} in {}", from.display(), to.display(), guild_id);
+         fs::rename(from, to)?;
+      }
+   }
+   Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+   use crate::audio::audio_source::tests::{lock_env, setup_temp_directories};
+   use futures::executor::block_on;
+   use serenity::model::id::UserId;
+   use std::fs::File;
+
+   fn user(id: u64, name: &str) -> User {
+      let mut user = User::default();
+      user.id = UserId::new(id);
+      user.name = name.to_string();
+      user
+   }
+
+   #[test]
+   fn test_username_clip_migrated_to_id() -> Result<(), Error> {
+      let _lock = lock_env();
+      let dir = setup_temp_directories()?;
+      let guild = dir.path().join("1");
+      File::create(guild.join("clip.meta.toml"))?;
+      let guild_id = GuildId::new(1);
+      let user = user(42, "clip");
+
+      // resolving the entrance migrates it
+      assert_eq!(block_on(clip_name(&guild_id, &user)), "42");
+      assert!(guild.join("42.mp3").is_file());
+      assert!(guild.join("42.meta.toml").is_file());
+      assert!(!guild.join("clip.mp3").exists());
+      assert!(!block_on(migrate_user(&guild_id, &user))?);
+
+      dir.close()?;
+      Ok(())
+   }
+
+   #[test]
+   fn test_exit_clip_resolved_and_migrated() -> Result<(), Error> {
+      let _lock = lock_env();
+      let dir = setup_temp_directories()?;
+      let guild = dir.path().join("1");
+      let guild_id = GuildId::new(1);
+      let user = user(42, "someone");
+
+      assert_eq!(block_on(exit_clip_name(&guild_id, &user)), None);
+      File::create(guild.join("someone.exit.ogg"))?;
+      assert_eq!(block_on(exit_clip_name(&guild_id, &user)), Some("42.exit".to_string()));
+      assert!(guild.join("42.exit.ogg").is_file());
+      assert!(!guild.join("someone.exit.ogg").exists());
+
+      dir.close()?;
+      Ok(())
+   }
+
+   #[test]
+   fn test_existing_id_clip_not_overwritten() -> Result<(), Error> {
+      let _lock = lock_env();
+      let dir = setup_temp_directories()?;
+      let guild = dir.path().join("1");
+      File::create(guild.join("42.wav"))?;
+
+      assert!(!block_on(migrate_user(&GuildId::new(1), &user(42, "clip")))?);
+      assert!(guild.join("clip.mp3").is_file());
+
+      dir.close()?;
+      Ok(())
+   }
+
+   #[test]
+   fn test_member_clips_recognized() {
+      assert!(is_member_clip("42"));
+      assert!(is_member_clip("42.exit"));
+      assert!(is_member_clip("someone.exit"));
+      assert!(!is_member_clip("airhorn"));
+      assert!(!is_member_clip("1984 theme"));
+      assert!(!is_member_clip(""));
+   }
+}
@@ -0,0 +1,181 @@
+use log::{error, info};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serenity::model::id::GuildId;
+use std::{
+   collections::{BTreeMap, BTreeSet},
+   path::Path,
+   sync::{
+      atomic::{AtomicBool, Ordering},
+      RwLock,
+   },
+   time::Duration,
+};
+use tokio::{sync::mpsc, time};
+
+use super::audio_source::{self, ClipInfo, EXTENSIONS};
+
+// How long the directory must be quiet before changed guilds are scanned again, so a burst of events costs one scan
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+// Each guild's clips as last listed, which can only be trusted while the directory is watched
+static CLIPS: RwLock<BTreeMap<GuildId, Vec<ClipInfo>>> = RwLock::new(BTreeMap::new());
+static WATCHING: AtomicBool = AtomicBool::new(false);
+
+/// The guild's clips as last listed, if they are known to still be current.
+pub fn cached(guild_id: &GuildId) -> Option<Vec<ClipInfo>> {
+   if !WATCHING.load(Ordering::SeqCst) {
+      return None;
+   }
+   CLIPS
+      .read()
+      .unwrap_or_else(|poisoned| poisoned.into_inner())
+      .get(guild_id)
+      .cloned()
+}
+
+pub fn store(guild_id: &GuildId, clips: Vec<ClipInfo>) {
+   if WATCHING.load(Ordering::SeqCst) {
+      CLIPS
+         .write()
+         .unwrap_or_else(|poisoned| poisoned.into_inner())
+         .insert(*guild_id, clips);
+   }
+}
+
+/// Forgets the guild's clips so the next lookup lists them again, for changes that shouldn't wait for the watcher.
+pub fn invalidate(guild_id: &GuildId) {
+   CLIPS
+      .write()
+      .unwrap_or_else(|poisoned| poisoned.into_inner())
+      .remove(guild_id);
+}
+
+/// Watches the audio file directory, listing a guild's clips again shortly after its files are created, renamed,
+/// changed or deleted. The library is only kept while the returned watcher lives.
+pub fn watch(dir: &Path) -> notify::Result<RecommendedWatcher> {
+   let (sender, receiver) = mpsc::unbounded_channel();
+   let root = dir.to_owned();
+   let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| match event {
+      Ok(event) if !matches!(event.kind, EventKind::Access(_)) => {
+         for guild_id in event.paths.iter().filter_map(|path| changed_guild(&root, path)) {
+            // the receiver only goes away with the runtime, when there is nothing left to update
+            let _ = sender.send(guild_id);
+         }
+      }
+      Ok(_) => (),
+      Err(err) => error!("Error watching the audio file directory: {:?}", err),
+   })?;
+   watcher.watch(dir, RecursiveMode::Recursive)?;
+
+   WATCHING.store(true, Ordering::SeqCst);
+   tokio::spawn(rescan_changes(receiver));
+   info!("Watching {} for clip changes", dir.display());
+   Ok(watcher)
+}
+
+async fn rescan_changes(mut receiver: mpsc::UnboundedReceiver<GuildId>) {
+   while let Some(guild_id) = receiver.recv().await {
+      let mut changed = BTreeSet::from([guild_id]);
+      while let Ok(Some(guild_id)) = time::timeout(DEBOUNCE, receiver.recv()).await {
+         changed.insert(guild_id);
+      }
+      for guild_id in changed {
+         invalidate(&guild_id);
+         if let Err(err) = audio_source::clips(&guild_id).await {
+            error!(
+               "Could not list the clips of {:?} after they changed: {:?}",
+               guild_id, err
+            );
+         }
+      }
+   }
+}
+
+/// The guild whose clips a changed path belongs to, ignoring the bot's own bookkeeping files.
+fn changed_guild(root: &Path, path: &Path) -> Option<GuildId> {
+   let relative = path.strip_prefix(root).ok()?;
+   let mut components = relative.components();
+   let guild_id = components
+      .next()?
+      .as_os_str()
+      .to_str()?
+      .parse()
+      .ok()
+      .filter(|id| *id != 0)?;
+   let file_name = components.next()?.as_os_str().to_str()?;
+   if components.next().is_some() {
+      return None;
+   }
+
+   let is_clip = file_name
+      .rsplit_once('.')
+      .is_some_and(|(_, extension)| EXTENSIONS.contains(&extension));
+   (is_clip || file_name.ends_with(".meta.toml")).then(|| GuildId::new(guild_id))
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+   use crate::audio::audio_source::tests::{lock_env, setup_temp_directories, silent_wav};
+   use std::fs;
+
+   #[test]
+   fn test_only_clip_changes_matter() {
+      let root = Path::new("/clips");
+
+      assert_eq!(changed_guild(root, &root.join("1/airhorn.mp3")), Some(GuildId::new(1)));
+      assert_eq!(
+         changed_guild(root, &root.join("1/airhorn.meta.toml")),
+         Some(GuildId::new(1))
+      );
+      assert_eq!(changed_guild(root, &root.join("1/index.toml")), None);
+      assert_eq!(changed_guild(root, &root.join("1/history.toml")), None);
+      assert_eq!(changed_guild(root, &root.join("1/nested/airhorn.mp3")), None);
+      assert_eq!(changed_guild(root, &root.join("api_tokens.toml")), None);
+      assert_eq!(changed_guild(root, &root.join("not_a_guild/airhorn.mp3")), None);
+   }
+
+   /// Stops trusting the library once a test is done with it, even if the test fails.
+   struct Unwatch(GuildId);
+
+   impl Drop for Unwatch {
+      fn drop(&mut self) {
+         WATCHING.store(false, Ordering::SeqCst);
+         invalidate(&self.0);
+      }
+   }
+
+   #[test]
+   fn test_new_clips_noticed() {
+      let _lock = lock_env();
+      let dir = setup_temp_directories().unwrap();
+      let guild_id = GuildId::new(1);
+      let _unwatch = Unwatch(guild_id);
+
+      let names = tokio::runtime::Runtime::new().unwrap().block_on(async {
+         let _watcher = watch(dir.path()).unwrap();
+         assert_eq!(
+            audio_source::clip_names(&guild_id).await.unwrap(),
+            ["another_clip", "clip"]
+         );
+         assert!(cached(&guild_id).is_some());
+         fs::write(dir.path().join("1").join("new_clip.wav"), silent_wav()).unwrap();
+
+         let mut names = Vec::new();
+         for _ in 0..50 {
+            time::sleep(Duration::from_millis(100)).await;
+            names = cached(&guild_id)
+               .unwrap_or_default()
+               .into_iter()
+               .map(|clip| clip.name)
+               .collect::<Vec<_>>();
+            if names.len() == 3 {
+               break;
+            }
+         }
+         names
+      });
+
+      assert_eq!(names, ["another_clip", "clip", "new_clip"]);
+   }
+}
@@ -1,3 +1,11 @@
 pub mod audio_source;
+pub mod clip_index;
+pub mod clip_metadata;
 pub mod connection_data;
+pub mod decode;
+pub mod entrances;
+pub mod library;
 pub mod playback;
+pub mod random;
+pub mod tracks;
+pub mod trim;
@@ -1,7 +1,18 @@
 use crate::{
-   audio::{audio_source, connection_data::ConnectionData},
-   call_result::CallResult,
+   audio::{
+      audio_source::{self, ClipInfo},
+      clip_index, clip_metadata,
+      connection_data::ConnectionData,
+      entrances,
+      tracks::{self, PlayingTracks, TrackGain, TrackLoudnessGain, TrackTitle},
+   },
+   call_result::{CallError, CallResult},
+   guild_config::{self, GuildConfigs},
+   history::{self, Origin, PlaySource},
+   locale::Message,
+   shutdown,
 };
+use log::error;
 use reqwest::Client;
 use serenity::{
    client::Context,
@@ -12,13 +23,44 @@ use serenity::{
    prelude::Mutex,
 };
 use songbird::{
-   error::{JoinError, JoinResult},
+   error::JoinResult,
    input::{Input, YoutubeDl},
    tracks::Track,
    Call, Songbird,
 };
-use std::{io::ErrorKind, sync::Arc};
-use tokio::sync::MutexGuard;
+use std::sync::Arc;
+use tokio::{sync::MutexGuard, task};
+
+/// How a new track should interact with whatever the bot is already playing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlayMode {
+   /// Mix the track in immediately on top of anything already playing
+   Overlap,
+   /// Add the track to the end of the guild's queue
+   Queue,
+}
+
+impl PlayMode {
+   fn for_guild(queue: bool) -> Self {
+      if queue {
+         PlayMode::Queue
+      } else {
+         PlayMode::Overlap
+      }
+   }
+}
+
+/// A source to play along with the details needed to manage it once it starts.
+pub struct PlayRequest<'a> {
+   pub source: Input,
+   pub title: &'a str,
+   /// Clip-specific multiplier applied on top of the guild volume
+   pub gain: f32,
+   /// Multiplier that brings the source to the guild's loudness target
+   pub loudness_gain: f32,
+   pub mode: PlayMode,
+   pub origin: Origin,
+}
 
 pub async fn get_manager(ctx: &Context) -> Arc<Songbird> {
    songbird::get(ctx)
@@ -26,32 +68,173 @@ pub async fn get_manager(ctx: &Context) -> Arc<Songbird> {
       .expect("Songbird voice client should have been placed during initialization")
 }
 
-async fn play_source(mut call: MutexGuard<'_, Call>, source: Input, volume: f32) {
-   let track = Track::new(source).volume(volume);
-   call.play(track);
+pub async fn get_tracks(ctx: &Context) -> Arc<PlayingTracks> {
+   ctx.data
+      .read()
+      .await
+      .get::<PlayingTracks>()
+      .cloned()
+      .expect("Playing tracks should have been placed during initialization")
 }
 
-pub async fn stop(ctx: &Context, connect_to: ConnectionData) -> CallResult {
-   let manager = get_manager(ctx).await;
+async fn play_source(
+   mut call: MutexGuard<'_, Call>, tracks: &PlayingTracks, guild_id: GuildId, volume: f32, request: PlayRequest<'_>,
+) {
+   let track = Track::new(request.source).volume(volume * request.gain * request.loudness_gain);
+   let handle = match request.mode {
+      PlayMode::Overlap => call.play(track),
+      PlayMode::Queue => call.enqueue(track).await,
+   };
+   {
+      let mut typemap = handle.typemap().write().await;
+      typemap.insert::<TrackTitle>(request.title.to_owned());
+      typemap.insert::<TrackGain>(request.gain);
+      typemap.insert::<TrackLoudnessGain>(request.loudness_gain);
+   }
+   tracks.add(guild_id, handle).await;
+   let (title, origin) = (request.title.to_owned(), request.origin);
+   task::spawn_blocking(move || history::record(&guild_id, &title, origin));
+}
 
+pub async fn stop_with_manager(manager: Arc<Songbird>, connect_to: ConnectionData) -> CallResult {
    if let Some(call) = manager.get(connect_to.guild) {
       let mut locked = call.lock().await;
       if let Some(channel_id) = locked.current_channel() {
          if channel_id == connect_to.channel.into() {
+            locked.queue().stop();
             locked.stop();
-            return CallResult::success("Playback stopped");
+            return Ok(Message::new("playback_stopped"));
          }
       }
    };
-   CallResult::failure(
-      "Bot is not currently in your channel".to_string(),
-      "Bot in a different channel than requestor",
-   )
+   Err(CallError::WrongChannel)
+}
+
+pub async fn stop(ctx: &Context, connect_to: ConnectionData) -> CallResult {
+   stop_with_manager(get_manager(ctx).await, connect_to).await
+}
+
+pub async fn skip(ctx: &Context, connect_to: ConnectionData) -> CallResult {
+   let manager = get_manager(ctx).await;
+
+   if let Some(call) = manager.get(connect_to.guild) {
+      let locked = call.lock().await;
+      if locked.current_channel() == Some(connect_to.channel.into()) {
+         let queue = locked.queue();
+         return match queue.current() {
+            Some(current) => match queue.skip() {
+               Ok(_) => Ok(Message::new("skipped").with("clip", tracks::title(&current).await)),
+               Err(err) => Err(CallError::failed(Message::new("failed_skip"), err)),
+            },
+            None => Ok(Message::new("nothing_queued")),
+         };
+      }
+   }
+   Err(CallError::WrongChannel)
+}
+
+pub async fn queue(ctx: &Context, guild_id: GuildId) -> CallResult {
+   if !guild_config::get(ctx).await.get(guild_id).await.queue {
+      return Ok(Message::new("queue_disabled"));
+   }
+
+   let handles = match get_manager(ctx).await.get(guild_id) {
+      Some(call) => call.lock().await.queue().current_queue(),
+      None => Vec::new(),
+   };
+   let mut lines = Vec::new();
+   for (position, handle) in handles.iter().enumerate() {
+      let title = tracks::title(handle).await;
+      lines.push(match position {
+         0 => Message::new("now_playing").with("clip", title),
+         _ => Message::Plain(format!("{position}. {title}")),
+      });
+   }
+   if lines.is_empty() {
+      lines.push(Message::new("queue_empty"));
+   }
+   Ok(Message::Lines(lines))
+}
+
+/// Sets the guild's default volume, or a single clip's gain when a clip is named, and applies it to playing tracks.
+pub async fn set_volume(ctx: &Context, guild_id: GuildId, clip: Option<&str>, volume: f32) -> CallResult {
+   let configs = guild_config::get(ctx).await;
+   let tracks = get_tracks(ctx).await;
+
+   let guild_volume = match clip {
+      Some(name) => {
+         let saved = match clip_metadata::load(name, &guild_id) {
+            Ok(mut metadata) => {
+               metadata.gain = volume;
+               clip_metadata::save(name, &guild_id, &metadata).await
+            }
+            Err(err) => Err(err),
+         };
+         match saved {
+            Ok(_) => configs.get(guild_id).await.volume,
+            Err(err) => {
+               return Err(CallError::for_clip(
+                  name,
+                  Message::new("failed_save_clip_volume").with("clip", name),
+                  err,
+               ))
+            }
+         }
+      }
+      None => match configs.update(guild_id, |config| config.volume = volume).await {
+         Ok(config) => config.volume,
+         Err(err) => return Err(CallError::failed(Message::new("failed_save_volume"), err)),
+      },
+   };
+
+   for handle in tracks.current(guild_id).await {
+      let mut typemap = handle.typemap().write().await;
+      if clip.is_some_and(|name| {
+         typemap
+            .get::<TrackTitle>()
+            .is_some_and(|title| title.eq_ignore_ascii_case(name))
+      }) {
+         typemap.insert::<TrackGain>(volume);
+      }
+      let gain = typemap.get::<TrackGain>().copied().unwrap_or(1.0);
+      let loudness_gain = typemap.get::<TrackLoudnessGain>().copied().unwrap_or(1.0);
+      // a track may finish between listing and updating it, which is harmless
+      let _ = handle.set_volume(guild_volume * gain * loudness_gain);
+   }
+
+   let percent = (volume * 100.0).round();
+   match clip {
+      Some(name) => Ok(Message::new("clip_volume_set")
+         .with("clip", name)
+         .with("percent", percent)),
+      None => Ok(Message::new("volume_set").with("percent", percent)),
+   }
+}
+
+pub async fn volume(ctx: &Context, guild_id: GuildId, clip: Option<&str>) -> CallResult {
+   let guild_volume = (guild_config::get(ctx).await.get(guild_id).await.volume * 100.0).round();
+   match clip {
+      Some(name) => match clip_metadata::load(name, &guild_id) {
+         Ok(metadata) => Ok(Message::new("clip_volume")
+            .with("clip", name)
+            .with("percent", (metadata.gain * 100.0).round())
+            .with("server", guild_volume)),
+         Err(err) => Err(CallError::for_clip(
+            name,
+            Message::new("failed_read_clip_volume").with("clip", name),
+            err,
+         )),
+      },
+      None => Ok(Message::new("volume").with("percent", guild_volume)),
+   }
 }
 
 async fn join_connection_with_manager(
    manager: Arc<Songbird>, connect_to: ConnectionData,
-) -> Result<Arc<Mutex<Call>>, JoinError> {
+) -> Result<Arc<Mutex<Call>>, CallError> {
+   if shutdown::in_progress() {
+      return Err(CallError::ShuttingDown);
+   }
    let call = manager.get_or_insert(connect_to.guild);
    let current_channel_id = { call.lock().await.current_channel() };
 
@@ -62,76 +245,212 @@ async fn join_connection_with_manager(
    }
    match manager.join(connect_to.guild, connect_to.channel).await {
       JoinResult::Ok(call) => Ok(call),
-      JoinResult::Err(err) => Err(err),
+      JoinResult::Err(err) => Err(err.into()),
    }
 }
 
-pub async fn join_connection(ctx: &Context, connect_to: ConnectionData) -> Result<Arc<Mutex<Call>>, JoinError> {
+pub async fn join_connection(ctx: &Context, connect_to: ConnectionData) -> Result<Arc<Mutex<Call>>, CallError> {
    let manager = get_manager(ctx).await;
 
    join_connection_with_manager(manager, connect_to).await
 }
 
 async fn join_connection_with_manager_and_play(
-   manager: Arc<Songbird>, connect_to: ConnectionData, source: Input, volume: f32,
-) -> Result<(), JoinError> {
-   match join_connection_with_manager(manager, connect_to).await {
-      Ok(call) => {
-         play_source(call.lock().await, source, volume).await;
-         Ok(())
+   manager: Arc<Songbird>, configs: &GuildConfigs, tracks: &PlayingTracks, connect_to: ConnectionData,
+   request: PlayRequest<'_>,
+) -> Result<(), CallError> {
+   let guild_id = connect_to.guild;
+   let call = join_connection_with_manager(manager, connect_to).await?;
+   let volume = configs.get(guild_id).await.volume;
+   play_source(call.lock().await, tracks, guild_id, volume, request).await;
+   Ok(())
+}
+
+pub async fn join_connection_and_play(
+   ctx: &Context, connect_to: ConnectionData, request: PlayRequest<'_>,
+) -> Result<(), CallError> {
+   join_connection_with_manager_and_play(
+      get_manager(ctx).await,
+      &*guild_config::get(ctx).await,
+      &*get_tracks(ctx).await,
+      connect_to,
+      request,
+   )
+   .await
+}
+
+/// Joins the channel, announcing the bot with the guild's `myman` clip if it has one.
+pub async fn join_connection_and_announce(
+   ctx: &Context, connect_to: ConnectionData, user: Option<UserId>,
+) -> Result<(), CallError> {
+   match audio_source::file("myman", &connect_to.guild).await {
+      Ok(source) => {
+         let clip = clip_index::clip("myman", &connect_to.guild).await;
+         let request = PlayRequest {
+            source,
+            title: "myman",
+            gain: clip_gain("myman", &connect_to.guild),
+            loudness_gain: loudness_gain(&*guild_config::get(ctx).await, clip.as_ref(), connect_to.guild).await,
+            mode: PlayMode::Overlap,
+            origin: Origin {
+               source: PlaySource::Summon,
+               user,
+            },
+         };
+         join_connection_and_play(ctx, connect_to, request).await
       }
-      Err(err) => Err(err),
+      Err(_) => join_connection(ctx, connect_to).await.map(|_| ()),
    }
 }
 
-pub async fn join_connection_and_play(
-   ctx: &Context, connect_to: ConnectionData, source: Input, volume: f32,
-) -> Result<(), JoinError> {
-   join_connection_with_manager_and_play(get_manager(ctx).await, connect_to, source, volume).await
+/// The gain that brings a clip to the guild's loudness target, if the guild normalizes loudness.
+async fn loudness_gain(configs: &GuildConfigs, clip: Option<&ClipInfo>, guild_id: GuildId) -> f32 {
+   let config = configs.get(guild_id).await;
+   match clip {
+      Some(clip) if config.normalize_loudness => clip_index::loudness_gain(clip, config.loudness_target),
+      _ => 1.0,
+   }
+}
+
+fn clip_gain(name: &str, guild_id: &GuildId) -> f32 {
+   clip_metadata::load(name, guild_id)
+      .map(|metadata| metadata.gain)
+      .unwrap_or_else(|err| {
+         error!("Could not read metadata for {name} in {guild_id}, playing at default gain: {err}");
+         1.0
+      })
 }
 
 pub async fn play_entrance(ctx: Context, guild_id: GuildId, channel_id: ChannelId, user_id: UserId) -> CallResult {
    match user_id.to_user(&ctx).await {
       Ok(user) => match user {
-         User { bot: true, .. } => CallResult::success(format!("A bot joined a channel: {}", user.name)),
+         User { bot: true, .. } => Ok(Message::new("bot_joined").with("user", &user.name)),
          _ => {
-            play_file(
-               &ctx,
-               &user.name,
+            let configs = guild_config::get(&ctx).await;
+            let config = configs.get(guild_id).await;
+            if !config.entrances {
+               return Ok(Message::new("entrances_disabled"));
+            }
+            play_file_with_mode(
+               get_manager(&ctx).await,
+               &configs,
+               &*get_tracks(&ctx).await,
+               &entrances::clip_name(&guild_id, &user).await,
                ConnectionData {
                   guild: guild_id,
                   channel: channel_id,
                },
+               PlayMode::for_guild(config.queue && !config.overlap_entrances),
+               Origin::new(PlaySource::Entrance, user_id),
             )
             .await
          }
       },
-      Err(err) => CallResult::failure("Could not get user name", err),
+      Err(err) => Err(CallError::failed(Message::new("failed_get_user"), err)),
    }
 }
 
-pub async fn play_youtube(ctx: &Context, client: Client, url: &str, connect_to: ConnectionData) -> CallResult {
-   if !url.starts_with("http") {
-      return CallResult::success(format!("{url} is not a valid URL"));
+pub async fn play_exit(ctx: Context, guild_id: GuildId, channel_id: ChannelId, user_id: UserId) -> CallResult {
+   let configs = guild_config::get(&ctx).await;
+   let config = configs.get(guild_id).await;
+   if !config.exit_sounds {
+      return Ok(Message::new("exits_disabled"));
    }
 
-   match join_connection_and_play(ctx, connect_to, YoutubeDl::new(client, url.to_owned()).into(), 1.0).await {
-      Ok(_) => CallResult::success(format!("Playing {url}")),
-      Err(err) => CallResult::failure("Failed to load youtube content", err),
+   match user_id.to_user(&ctx).await {
+      Ok(user) => match user {
+         User { bot: true, .. } => Ok(Message::new("bot_left").with("user", &user.name)),
+         _ => match entrances::exit_clip_name(&guild_id, &user).await {
+            Some(name) => {
+               play_file_with_mode(
+                  get_manager(&ctx).await,
+                  &configs,
+                  &*get_tracks(&ctx).await,
+                  &name,
+                  ConnectionData {
+                     guild: guild_id,
+                     channel: channel_id,
+                  },
+                  PlayMode::for_guild(config.queue && !config.overlap_entrances),
+                  Origin::new(PlaySource::Exit, user_id),
+               )
+               .await
+            }
+            None => Ok(Message::new("no_exit_sound").with("user", &user.name)),
+         },
+      },
+      Err(err) => Err(CallError::failed(Message::new("failed_get_user"), err)),
    }
 }
 
-pub async fn play_file_with_manager(manager: Arc<Songbird>, name: &str, connect_to: ConnectionData) -> CallResult {
-   match audio_source::file(name, &connect_to.guild).await {
-      Ok(source) => match join_connection_with_manager_and_play(manager, connect_to, source, 1.0).await {
-         Ok(_) => CallResult::success(format!("Playing {name}")),
-         Err(err) => CallResult::failure(format!("Failed to load file for {name}"), err),
-      },
-      Err(err) if err.kind() == ErrorKind::NotFound => CallResult::success(format!("Audio file not found for {name}")),
-      Err(err) => CallResult::failure(format!("Failed to load file for {name}"), err),
+pub async fn play_youtube(
+   ctx: &Context, client: Client, url: &str, connect_to: ConnectionData, origin: Origin,
+) -> CallResult {
+   if !url.starts_with("http") {
+      return Err(CallError::Invalid(Message::new("invalid_url").with("url", url)));
+   }
+
+   let mode = PlayMode::for_guild(guild_config::get(ctx).await.get(connect_to.guild).await.queue);
+   let request = PlayRequest {
+      source: YoutubeDl::new(client, url.to_owned()).into(),
+      title: url,
+      gain: 1.0,
+      loudness_gain: 1.0,
+      mode,
+      origin,
+   };
+   join_connection_and_play(ctx, connect_to, request).await?;
+   match mode {
+      PlayMode::Queue => Ok(Message::new("queued").with("clip", url)),
+      PlayMode::Overlap => Ok(Message::new("playing").with("clip", url)),
    }
 }
 
-pub async fn play_file(ctx: &Context, name: &str, connect_to: ConnectionData) -> CallResult {
-   play_file_with_manager(get_manager(ctx).await, name, connect_to).await
+async fn play_file_with_mode(
+   manager: Arc<Songbird>, configs: &GuildConfigs, tracks: &PlayingTracks, name: &str, connect_to: ConnectionData,
+   mode: PlayMode, origin: Origin,
+) -> CallResult {
+   let guild_id = connect_to.guild;
+   let source = audio_source::file(name, &guild_id)
+      .await
+      .map_err(|err| CallError::for_clip(name, Message::new("failed_load_clip").with("clip", name), err))?;
+   let clip = clip_index::clip(name, &guild_id).await;
+   let request = PlayRequest {
+      source,
+      title: name,
+      gain: clip_gain(name, &guild_id),
+      loudness_gain: loudness_gain(configs, clip.as_ref(), guild_id).await,
+      mode,
+      origin,
+   };
+   join_connection_with_manager_and_play(manager, configs, tracks, connect_to, request).await?;
+
+   let summary = clip.and_then(|clip| clip_index::summary(&clip));
+   let message = match (mode, summary) {
+      (PlayMode::Queue, Some(summary)) => Message::new("queued_with_details").with_message("details", summary),
+      (PlayMode::Queue, None) => Message::new("queued"),
+      (PlayMode::Overlap, Some(summary)) => Message::new("playing_with_details").with_message("details", summary),
+      (PlayMode::Overlap, None) => Message::new("playing"),
+   };
+   Ok(message.with("clip", name))
+}
+
+pub async fn play_file_with_manager(
+   manager: Arc<Songbird>, configs: Arc<GuildConfigs>, tracks: Arc<PlayingTracks>, name: &str,
+   connect_to: ConnectionData, origin: Origin,
+) -> CallResult {
+   let mode = PlayMode::for_guild(configs.get(connect_to.guild).await.queue);
+   play_file_with_mode(manager, &configs, &tracks, name, connect_to, mode, origin).await
+}
+
+pub async fn play_file(ctx: &Context, name: &str, connect_to: ConnectionData, origin: Origin) -> CallResult {
+   play_file_with_manager(
+      get_manager(ctx).await,
+      guild_config::get(ctx).await,
+      get_tracks(ctx).await,
+      name,
+      connect_to,
+      origin,
+   )
+   .await
 }
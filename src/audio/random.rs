@@ -0,0 +1,102 @@
+use rand::{seq::SliceRandom, Rng};
+use serenity::{
+   client::Context,
+   model::id::GuildId,
+   prelude::{Mutex, TypeMapKey},
+};
+use std::{
+   collections::{HashMap, VecDeque},
+   io::Error,
+   sync::Arc,
+};
+
+use super::{audio_source, entrances};
+
+/// How many of a guild's most recent random picks are avoided when choosing the next one.
+const RECENT_PICKS: usize = 5;
+
+/// The clips most recently chosen by `/random` in each guild, oldest first.
+#[derive(Default)]
+pub struct RecentPicks {
+   picks: Mutex<HashMap<GuildId, VecDeque<String>>>,
+}
+
+impl TypeMapKey for RecentPicks {
+   type Value = Arc<RecentPicks>;
+}
+
+impl RecentPicks {
+   /// Chooses a random clip whose name contains the filter, or `None` if nothing matches. Members' entrance and exit
+   /// clips are never picked.
+   pub async fn pick(&self, guild_id: GuildId, filter: Option<&str>) -> Result<Option<String>, Error> {
+      let filter = filter.unwrap_or_default().trim().to_lowercase();
+      let names = audio_source::clip_names(&guild_id)
+         .await?
+         .into_iter()
+         .filter(|name| !entrances::is_member_clip(name) && name.to_lowercase().contains(&filter))
+         .collect::<Vec<_>>();
+
+      let mut picks = self.picks.lock().await;
+      let recent = picks.entry(guild_id).or_default();
+      let pick = choose(&names, recent, &mut rand::thread_rng());
+      if let Some(name) = &pick {
+         recent.push_back(name.clone());
+         if recent.len() > RECENT_PICKS {
+            recent.pop_front();
+         }
+      }
+      Ok(pick)
+   }
+}
+
+pub async fn get_recent_picks(ctx: &Context) -> Arc<RecentPicks> {
+   ctx.data
+      .read()
+      .await
+      .get::<RecentPicks>()
+      .cloned()
+      .expect("Recent picks should have been placed during initialization")
+}
+
+/// Picks a name that wasn't chosen recently, forgetting the oldest picks first when the filter leaves too few names.
+fn choose<R: Rng>(names: &[String], recent: &VecDeque<String>, rng: &mut R) -> Option<String> {
+   (0..=recent.len()).rev().find_map(|avoided| {
+      let avoid = recent.iter().rev().take(avoided).collect::<Vec<_>>();
+      let candidates = names.iter().filter(|name| !avoid.contains(name)).collect::<Vec<_>>();
+      candidates.choose(rng).map(|name| name.to_string())
+   })
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   fn names(names: &[&str]) -> Vec<String> {
+      names.iter().map(|name| name.to_string()).collect()
+   }
+
+   #[test]
+   fn test_recent_picks_avoided() {
+      let all = names(&["a", "b", "c", "d", "e", "f"]);
+      let mut recent = VecDeque::new();
+      let mut rng = rand::thread_rng();
+
+      for _ in 0..30 {
+         let pick = choose(&all, &recent, &mut rng).unwrap();
+         assert!(!recent.contains(&pick));
+         recent.push_back(pick);
+         if recent.len() > RECENT_PICKS {
+            recent.pop_front();
+         }
+      }
+   }
+
+   #[test]
+   fn test_oldest_picks_forgotten_when_few_names() {
+      let all = names(&["a", "b"]);
+      let recent = VecDeque::from(names(&["b", "a"]));
+
+      assert_eq!(choose(&all, &recent, &mut rand::thread_rng()), Some("b".to_string()));
+      assert_eq!(choose(&[], &recent, &mut rand::thread_rng()), None);
+   }
+}
@@ -0,0 +1,90 @@
+use serenity::{
+   model::id::GuildId,
+   prelude::{Mutex, TypeMapKey},
+};
+use songbird::tracks::TrackHandle;
+use std::{collections::HashMap, sync::Arc};
+
+/// The user-facing name of a track, stored in its handle's type map.
+pub struct TrackTitle;
+
+impl TypeMapKey for TrackTitle {
+   type Value = String;
+}
+
+/// The clip-specific gain of a track, stored in its handle's type map so volume changes can preserve it.
+pub struct TrackGain;
+
+impl TypeMapKey for TrackGain {
+   type Value = f32;
+}
+
+/// The gain that brings a track to the guild's loudness target, stored in its handle's type map alongside its gain.
+pub struct TrackLoudnessGain;
+
+impl TypeMapKey for TrackLoudnessGain {
+   type Value = f32;
+}
+
+/// Handles for the tracks started in each guild, so they can be adjusted after they begin playing.
+#[derive(Default)]
+pub struct PlayingTracks {
+   tracks: Mutex<HashMap<GuildId, Vec<TrackHandle>>>,
+}
+
+impl TypeMapKey for PlayingTracks {
+   type Value = Arc<PlayingTracks>;
+}
+
+impl PlayingTracks {
+   /// Keeps the handle for a newly started track, forgetting any of the guild's tracks that have finished so the
+   /// handles don't pile up between calls to `current`.
+   pub async fn add(&self, guild_id: GuildId, handle: TrackHandle) {
+      let mut tracks = self.tracks.lock().await;
+      let mut current = live(tracks.remove(&guild_id).unwrap_or_default()).await;
+      current.push(handle);
+      tracks.insert(guild_id, current);
+   }
+
+   /// Returns the guild's tracks that are playing or queued, forgetting any that have finished.
+   pub async fn current(&self, guild_id: GuildId) -> Vec<TrackHandle> {
+      let mut tracks = self.tracks.lock().await;
+      let current = live(tracks.remove(&guild_id).unwrap_or_default()).await;
+      if !current.is_empty() {
+         tracks.insert(guild_id, current.clone());
+      }
+      current
+   }
+
+   /// Whether any guild still has a track playing or queued.
+   pub async fn any_playing(&self) -> bool {
+      let guild_ids = self.tracks.lock().await.keys().copied().collect::<Vec<_>>();
+      for guild_id in guild_ids {
+         if !self.current(guild_id).await.is_empty() {
+            return true;
+         }
+      }
+      false
+   }
+}
+
+async fn live(handles: Vec<TrackHandle>) -> Vec<TrackHandle> {
+   let mut live = Vec::with_capacity(handles.len());
+   for handle in handles {
+      // the driver drops finished tracks, after which their handles can no longer be queried
+      if handle.get_info().await.is_ok() {
+         live.push(handle);
+      }
+   }
+   live
+}
+
+pub async fn title(handle: &TrackHandle) -> String {
+   handle
+      .typemap()
+      .read()
+      .await
+      .get::<TrackTitle>()
+      .cloned()
+      .unwrap_or_else(|| "Unknown".to_string())
+}
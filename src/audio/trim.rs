@@ -0,0 +1,195 @@
+use std::{
+   fmt,
+   io::{Cursor, Error, ErrorKind},
+};
+
+use super::decode;
+use crate::locale::Message;
+
+// Samples quieter than this, about -50 dBFS, count as silence when stripping it
+const SILENCE_THRESHOLD: f32 = 0.003;
+
+/// Decoded audio as interleaved samples, ready to be cut and written back out.
+pub struct Pcm {
+   pub sample_rate: u32,
+   pub channels: usize,
+   samples: Vec<f32>,
+}
+
+impl Pcm {
+   pub fn decode(data: Vec<u8>, extension: &str) -> Result<Self, Error> {
+      let mut samples = Vec::new();
+      let spec = decode::decode(Box::new(Cursor::new(data)), extension, |_, decoded| {
+         samples.extend_from_slice(decoded)
+      })?;
+      Ok(Self {
+         sample_rate: spec.rate,
+         channels: spec.channels.count(),
+         samples,
+      })
+   }
+
+   pub fn frames(&self) -> usize {
+      self.samples.len() / self.channels.max(1)
+   }
+
+   pub fn duration_secs(&self) -> f64 {
+      self.frames() as f64 / f64::from(self.sample_rate)
+   }
+
+   /// Keeps only the audio between the start and end, in seconds from the beginning, cutting at the nearest sample.
+   pub fn trim(self, start: Option<f64>, end: Option<f64>) -> Result<Self, Error> {
+      let duration = self.duration_secs();
+      let start = start.unwrap_or(0.0);
+      let end = end.unwrap_or(duration);
+      if start < 0.0 || end <= start {
+         return Err(InvalidTrim::Range.into());
+      }
+      if start >= duration {
+         return Err(InvalidTrim::PastEnd { duration }.into());
+      }
+
+      let (rate, frames) = (f64::from(self.sample_rate), self.frames());
+      let to_frame = |seconds: f64| ((seconds * rate).round() as usize).min(frames);
+      Ok(self.slice(to_frame(start), to_frame(end)))
+   }
+
+   /// Removes the quiet stretches at the beginning and end.
+   pub fn strip_silence(self) -> Self {
+      let loud = |frame: &[f32]| frame.iter().any(|sample| sample.abs() > SILENCE_THRESHOLD);
+      let first = self.samples.chunks(self.channels.max(1)).position(loud);
+      let last = self.samples.chunks(self.channels.max(1)).rposition(loud);
+      match (first, last) {
+         (Some(first), Some(last)) => self.slice(first, last + 1),
+         _ => self.slice(0, 0),
+      }
+   }
+
+   /// Writes the audio as a 16-bit PCM WAV file.
+   pub fn to_wav(&self) -> Vec<u8> {
+      let channels = self.channels as u16;
+      let block_align = channels * 2;
+      let data_len = self.samples.len() as u32 * 2;
+
+      let mut wav = Vec::with_capacity(44 + data_len as usize);
+      wav.extend_from_slice(b"RIFF");
+      wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+      wav.extend_from_slice(b"WAVEfmt ");
+      wav.extend_from_slice(&16u32.to_le_bytes());
+      wav.extend_from_slice(&1u16.to_le_bytes());
+      wav.extend_from_slice(&channels.to_le_bytes());
+      wav.extend_from_slice(&self.sample_rate.to_le_bytes());
+      wav.extend_from_slice(&(self.sample_rate * u32::from(block_align)).to_le_bytes());
+      wav.extend_from_slice(&block_align.to_le_bytes());
+      wav.extend_from_slice(&16u16.to_le_bytes());
+      wav.extend_from_slice(b"data");
+      wav.extend_from_slice(&data_len.to_le_bytes());
+      for sample in &self.samples {
+         let sample = (sample.clamp(-1.0, 1.0) * f32::from(i16::MAX)).round() as i16;
+         wav.extend_from_slice(&sample.to_le_bytes());
+      }
+      wav
+   }
+
+   fn slice(mut self, first_frame: usize, end_frame: usize) -> Self {
+      self.samples.truncate(end_frame * self.channels);
+      self.samples.drain(..first_frame * self.channels);
+      self
+   }
+}
+
+/// Why a trim was refused, carried as the source of an `InvalidInput` error so it can be explained to the user.
+#[derive(Debug, PartialEq)]
+pub enum InvalidTrim {
+   /// The start is negative or doesn't come before the end
+   Range,
+   /// The start is past the end of a clip this many seconds long
+   PastEnd { duration: f64 },
+}
+
+impl InvalidTrim {
+   /// The reason behind an error, if it was raised for one.
+   pub fn of(err: &Error) -> Option<&Self> {
+      err.get_ref()?.downcast_ref()
+   }
+
+   pub fn message(&self) -> Message {
+      match self {
+         InvalidTrim::Range => Message::new("invalid_trim_range"),
+         InvalidTrim::PastEnd { duration } => Message::new("trim_past_end").with("seconds", format!("{duration:.2}")),
+      }
+   }
+}
+
+impl fmt::Display for InvalidTrim {
+   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+      self.message().fmt(f)
+   }
+}
+
+impl std::error::Error for InvalidTrim {}
+
+impl From<InvalidTrim> for Error {
+   fn from(reason: InvalidTrim) -> Self {
+      Error::new(ErrorKind::InvalidInput, reason)
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+   use crate::audio::audio_source::tests::wav;
+
+   // a tenth of a second of silence either side of a tenth of a second of noise, at 8 kHz
+   fn padded_noise() -> Pcm {
+      let samples = [vec![0; 800], vec![i16::MAX / 2; 800], vec![0; 800]].concat();
+      Pcm::decode(wav(8000, &samples), "wav").unwrap()
+   }
+
+   #[test]
+   fn test_trim_cuts_at_samples() {
+      let pcm = padded_noise();
+      assert_eq!(pcm.frames(), 2400);
+
+      let trimmed = pcm.trim(Some(0.05), Some(0.15)).unwrap();
+      assert_eq!(trimmed.frames(), 800);
+      assert_eq!(trimmed.samples[..400], [0.0; 400]);
+      assert!(trimmed.samples[400] > 0.4);
+   }
+
+   #[test]
+   fn test_trim_rejects_bad_ranges() {
+      let error = |start, end| padded_noise().trim(start, end).err().map(|err| err.kind());
+
+      assert_eq!(error(Some(0.2), Some(0.1)), Some(ErrorKind::InvalidInput));
+      assert_eq!(error(Some(-0.1), None), Some(ErrorKind::InvalidInput));
+      assert_eq!(error(Some(1.0), None), Some(ErrorKind::InvalidInput));
+      let err = padded_noise().trim(Some(1.0), Some(2.0)).err().unwrap();
+      assert_eq!(InvalidTrim::of(&err), Some(&InvalidTrim::PastEnd { duration: 0.3 }));
+      assert_eq!(err.to_string(), "The clip is only 0.30 seconds long");
+      // an end past the clip just keeps the rest of it
+      assert_eq!(padded_noise().trim(Some(0.2), Some(10.0)).unwrap().frames(), 800);
+   }
+
+   #[test]
+   fn test_silence_stripped() {
+      let stripped = padded_noise().strip_silence();
+
+      assert_eq!(stripped.frames(), 800);
+      assert!(stripped.samples.iter().all(|sample| *sample > 0.4));
+   }
+
+   #[test]
+   fn test_wav_round_trip() {
+      let pcm = padded_noise().trim(Some(0.1), None).unwrap();
+      let decoded = Pcm::decode(pcm.to_wav(), "wav").unwrap();
+
+      assert_eq!((decoded.sample_rate, decoded.channels), (8000, 1));
+      assert_eq!(decoded.frames(), pcm.frames());
+      assert!(decoded
+         .samples
+         .iter()
+         .zip(&pcm.samples)
+         .all(|(a, b)| (a - b).abs() < 0.001));
+   }
+}
@@ -0,0 +1,93 @@
+use log::error;
+use serenity::{
+   builder::{CreateAutocompleteResponse, CreateInteractionResponse},
+   client::Context,
+   model::application::CommandInteraction,
+};
+
+use crate::audio::audio_source;
+
+// Discord rejects autocomplete responses with more than 25 choices
+const MAX_CHOICES: usize = 25;
+
+pub async fn respond(ctx: &Context, interaction: &CommandInteraction) {
+   let choices = match (interaction.data.name.as_str(), interaction.data.autocomplete()) {
+      ("play", Some(option)) if option.name == "name" => clip_choices(interaction, option.value).await,
+      ("volume", Some(option)) if option.name == "clip" => clip_choices(interaction, option.value).await,
+      ("tags", Some(option)) if option.name == "clip" => clip_choices(interaction, option.value).await,
+      ("trim", Some(option)) if ["name", "clip"].contains(&option.name) => {
+         clip_choices(interaction, option.value).await
+      }
+      _ => Vec::new(),
+   };
+
+   let response = choices
+      .into_iter()
+      .fold(CreateAutocompleteResponse::new(), |response, name| {
+         response.add_string_choice(name.clone(), name)
+      });
+   if let Err(err) = interaction
+      .create_response(ctx, CreateInteractionResponse::Autocomplete(response))
+      .await
+   {
+      error!("Could not respond to autocomplete: {:?}", err);
+   }
+}
+
+async fn clip_choices(interaction: &CommandInteraction, query: &str) -> Vec<String> {
+   match interaction.guild_id {
+      Some(guild_id) => match audio_source::clip_names(&guild_id).await {
+         Ok(names) => rank_matches(names, query),
+         Err(err) => {
+            error!("Could not list clips for {}: {:?}", guild_id, err);
+            Vec::new()
+         }
+      },
+      None => Vec::new(),
+   }
+}
+
+/// Orders clip names that start with the query ahead of those that merely contain it, alphabetically within each.
+fn rank_matches(names: Vec<String>, query: &str) -> Vec<String> {
+   let query = query.trim().to_lowercase();
+   let (mut prefixed, mut contained): (Vec<_>, Vec<_>) = names
+      .into_iter()
+      .filter(|name| name.to_lowercase().contains(&query))
+      .partition(|name| name.to_lowercase().starts_with(&query));
+   prefixed.sort();
+   contained.sort();
+
+   prefixed.into_iter().chain(contained).take(MAX_CHOICES).collect()
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   fn names(names: &[&str]) -> Vec<String> {
+      names.iter().map(|name| name.to_string()).collect()
+   }
+
+   #[test]
+   fn test_prefix_matches_rank_first() {
+      let ranked = rank_matches(names(&["bruh", "airhorn", "bro", "sombrero"]), "br");
+
+      assert_eq!(ranked, names(&["bro", "bruh", "sombrero"]));
+   }
+
+   #[test]
+   fn test_match_is_case_insensitive() {
+      let ranked = rank_matches(names(&["bruh", "airhorn"]), " AIR");
+
+      assert_eq!(ranked, names(&["airhorn"]));
+   }
+
+   #[test]
+   fn test_empty_query_returns_everything_capped() {
+      let all = (0..40).map(|i| format!("clip{i:02}")).collect::<Vec<_>>();
+      let ranked = rank_matches(all.clone(), "");
+
+      assert_eq!(ranked.len(), MAX_CHOICES);
+      assert_eq!(ranked, all[..MAX_CHOICES]);
+   }
+}
@@ -1,29 +1,106 @@
-use log::error;
+use songbird::error::JoinError;
+use std::{error::Error, fmt, io};
 
-pub struct CallResult {
-   pub user_message: String,
-   pub underlying_error: Option<String>,
+use crate::locale::Message;
+
+/// The message to show for a call that went as planned, or why it couldn't.
+pub type CallResult = Result<Message, CallError>;
+
+#[derive(Debug)]
+pub enum CallError {
+   /// The request can't be carried out as given, with an explanation for whoever made it
+   Invalid(Message),
+   /// No sound file has the requested name
+   ClipNotFound(String),
+   /// The member asking isn't in a voice channel the bot can see
+   NotInVoiceChannel,
+   /// The bot is busy in a different voice channel than the member asking
+   WrongChannel,
+   /// Discord wouldn't let the bot into the voice channel, or took too long to
+   Join(JoinError),
+   /// The bot is leaving voice to shut down, so it won't start anything new
+   ShuttingDown,
+   /// Anything else, described by what was being attempted
+   Failed {
+      action: Message,
+      cause: Box<dyn Error + Send + Sync>,
+   },
 }
 
-impl CallResult {
-   pub fn success<T: ToString>(user_message: T) -> Self {
-      Self {
-         user_message: user_message.to_string(),
-         underlying_error: None,
+impl CallError {
+   pub fn failed<E: Into<Box<dyn Error + Send + Sync>>>(action: Message, cause: E) -> Self {
+      Self::Failed {
+         action,
+         cause: cause.into(),
       }
    }
 
-   pub fn failure<T: ToString, U: ToString>(user_message: T, underlying_error: U) -> Self {
-      Self {
-         user_message: user_message.to_string(),
-         underlying_error: Some(underlying_error.to_string()),
+   /// Treats a missing file as the clip not existing, and anything else as a failure to do the action.
+   pub fn for_clip(name: &str, action: Message, err: io::Error) -> Self {
+      match err.kind() {
+         io::ErrorKind::NotFound => Self::ClipNotFound(name.to_owned()),
+         _ => Self::failed(action, err),
       }
    }
+
+   /// Whether the error comes from what was asked for rather than from something going wrong.
+   pub fn is_expected(&self) -> bool {
+      matches!(
+         self,
+         CallError::Invalid(_)
+            | CallError::ClipNotFound(_)
+            | CallError::NotInVoiceChannel
+            | CallError::WrongChannel
+            | CallError::ShuttingDown
+      )
+   }
 }
 
-pub fn log_error_if_any(result: CallResult) -> CallResult {
-   if let Some(ref err) = result.underlying_error {
-      error!("Unexpected error occured during call: {}", err);
+impl fmt::Display for CallError {
+   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+      match self {
+         CallError::Invalid(reason) => write!(f, "{reason}"),
+         CallError::ClipNotFound(name) => write!(f, "No audio file named {name}"),
+         CallError::NotInVoiceChannel => f.write_str("Requestor is not in a voice channel"),
+         CallError::WrongChannel => f.write_str("Bot in a different channel than requestor"),
+         CallError::Join(err) => write!(f, "Failed to join the voice channel: {err}"),
+         CallError::ShuttingDown => f.write_str("Bot is shutting down"),
+         CallError::Failed { action, cause } => write!(f, "{action}: {cause}"),
+      }
+   }
+}
+
+impl Error for CallError {
+   fn source(&self) -> Option<&(dyn Error + 'static)> {
+      match self {
+         CallError::Join(err) => Some(err),
+         CallError::Failed { cause, .. } => Some(cause.as_ref()),
+         _ => None,
+      }
+   }
+}
+
+impl From<JoinError> for CallError {
+   fn from(err: JoinError) -> Self {
+      CallError::Join(err)
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn test_missing_clip_distinguished() {
+      let action = || Message::new("failed_load_clip").with("clip", "clip");
+      let not_found = CallError::for_clip("clip", action(), io::Error::from(io::ErrorKind::NotFound));
+      let denied = CallError::for_clip("clip", action(), io::Error::from(io::ErrorKind::PermissionDenied));
+
+      assert!(matches!(not_found, CallError::ClipNotFound(ref name) if name == "clip"));
+      assert!(not_found.is_expected());
+      assert!(
+         matches!(denied, CallError::Failed { ref action, .. } if action.to_string() == "Failed to load file for clip")
+      );
+      assert!(!denied.is_expected());
    }
-   result
 }
@@ -1,13 +1,23 @@
+use crate::{
+   audio::{
+      audio_source::{self, ClipInfo},
+      clip_index,
+   },
+   config,
+   guilds::{get_bot_guild_infos, get_guild},
+   locale::{Locale, Message},
+};
 use futures::{stream, StreamExt};
+use log::error;
 use serenity::{
    client::Context,
    model::{id::GuildId, user::User},
 };
-use std::env;
 
-use crate::guilds::{get_bot_guild_infos, get_guild};
+// Discord's limit on the length of an embed description
+const MAX_CONTENT_LENGTH: usize = 4096;
 
-pub async fn list(ctx: &Context, maybe_guild_id: Option<GuildId>, author: &User) -> String {
+pub async fn list(ctx: &Context, maybe_guild_id: Option<GuildId>, author: &User) -> Message {
    let author_guilds = if let Some(guild) = maybe_guild_id.and_then(|id| get_guild(ctx, id)) {
       vec![guild]
    } else {
@@ -28,14 +38,74 @@ pub async fn list(ctx: &Context, maybe_guild_id: Option<GuildId>, author: &User)
          .await
    };
 
-   let web_uri = env::var("WEB_URI").expect("Expected a web URI in the environment");
-   let mut content: String = String::new();
    if author_guilds.is_empty() {
-      content.push_str("You have no mutual servers with this bot");
+      return Message::new("no_mutual_servers");
+   }
+   let web_uri = &config::get(ctx).await.web_uri;
+   let mut lines = author_guilds
+      .iter()
+      .map(|guild| Message::Plain(format!("[**{}**]({}/clips/{})", guild.name, web_uri, guild.id)))
+      .collect::<Vec<_>>();
+   // from within a server, list its clips too, as far as fits in the message
+   if let (Some(guild_id), [_]) = (maybe_guild_id, author_guilds.as_slice()) {
+      match audio_source::clips(&guild_id).await {
+         Ok(clips) => {
+            let used = lines.iter().map(|line| line.to_string().len() + 1).sum::<usize>();
+            lines.extend(clip_lines(&clips, MAX_CONTENT_LENGTH - used));
+         }
+         Err(err) => error!("Could not list clips for {}: {:?}", guild_id, err),
+      }
    }
-   author_guilds.iter().for_each(|guild| {
-      content.push_str(&format!("[**{}**]({}/clips/{})\n", guild.name, web_uri, guild.id));
-   });
 
-   content
+   Message::Lines(lines)
+}
+
+/// Describes each clip on its own line, ending with a count of those left out once the lines would exceed `limit`.
+fn clip_lines(clips: &[ClipInfo], limit: usize) -> Vec<Message> {
+   let mut lines = Vec::new();
+   let mut length = 0;
+   for (shown, clip) in clips.iter().enumerate() {
+      let line = match clip_index::summary(clip) {
+         Some(summary) => Message::new("listed_clip_with_details")
+            .with("clip", &clip.name)
+            .with_message("details", summary),
+         None => Message::Plain(format!("- {}", clip.name)),
+      };
+      // measure the line in its longest language, so the list fits whichever one it is shown in
+      let line_length = Locale::ALL
+         .iter()
+         .map(|locale| line.text(*locale).len())
+         .max()
+         .unwrap_or_default();
+      // leave room for the note about the rest
+      if length + line_length + 1 > limit.saturating_sub(32) {
+         lines.push(Message::new("more_clips").with("count", clips.len() - shown));
+         break;
+      }
+      length += line_length + 1;
+      lines.push(line);
+   }
+   lines
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   fn clip(name: &str) -> ClipInfo {
+      ClipInfo {
+         name: name.to_string(),
+         ..Default::default()
+      }
+   }
+
+   #[test]
+   fn test_clip_lines_truncated() {
+      let clips = (0..10).map(|i| clip(&format!("clip{i}"))).collect::<Vec<_>>();
+
+      let lines = |clips, limit| Message::Lines(clip_lines(clips, limit)).to_string();
+
+      assert_eq!(lines(&clips[..2], 1000), "- clip0\n- clip1");
+      assert_eq!(lines(&clips, 32 + 16), "- clip0\n- clip1\n...and 8 more");
+   }
 }
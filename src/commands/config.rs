@@ -1,63 +1,96 @@
-use log::{error, info};
 use serenity::{
    all::{CreateCommand, CreateCommandOption},
-   client::Context,
    model::application::{Command, CommandOptionType},
 };
+use std::collections::HashMap;
 
-#[derive(Clone, Debug)]
+/// Translations of a name or description, as pairs of the locale code Discord knows each language by and the text.
+pub type Localizations<'a> = Vec<(&'a str, &'a str)>;
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct CommandOption<'a> {
    pub name: &'a str,
+   pub name_localizations: Localizations<'a>,
    pub description: &'a str,
+   pub description_localizations: Localizations<'a>,
    pub kind: CommandOptionType,
    pub required: bool,
+   pub autocomplete: bool,
 }
 
 impl Default for CommandOption<'_> {
    fn default() -> Self {
       Self {
          name: Default::default(),
+         name_localizations: Default::default(),
          description: Default::default(),
+         description_localizations: Default::default(),
          kind: CommandOptionType::String,
          required: false,
+         autocomplete: false,
       }
    }
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct CommandConfig<'a> {
    pub name: &'a str,
+   pub name_localizations: Localizations<'a>,
    pub description: &'a str,
+   pub description_localizations: Localizations<'a>,
    pub options: Vec<CommandOption<'a>>,
 }
 
 impl CommandConfig<'_> {
    pub fn is_equivalent(&self, command: &Command) -> bool {
       command.name == self.name
+         && same_localizations(&command.name_localizations, &self.name_localizations)
          && command.description == self.description
+         && same_localizations(&command.description_localizations, &self.description_localizations)
          && command.options.len() == self.options.len()
-         && command.options.iter().all(
-            |option| match self.options.iter().find(|config| config.name == option.name) {
-               Some(config) => {
-                  option.description == config.description
-                     && option.kind == config.kind
-                     && option.required == config.required
-               }
-               None => false,
-            },
-         )
+         // options are shown in the order they are registered, so a reordering needs updating too
+         && command.options.iter().zip(&self.options).all(|(option, config)| {
+            option.name == config.name
+               && same_localizations(&option.name_localizations, &config.name_localizations)
+               && option.description == config.description
+               && same_localizations(&option.description_localizations, &config.description_localizations)
+               && option.kind == config.kind
+               && option.required == config.required
+               && option.autocomplete == config.autocomplete
+         })
    }
 
-   pub async fn register_command(&self, ctx: &Context) {
-      info!("Registering command: {:?}", &self);
+   pub fn builder(&self) -> CreateCommand {
       let mut created = CreateCommand::new(self.name).description(self.description);
-      for option in &self.options {
-         created = created.add_option(
-            CreateCommandOption::new(option.kind, option.name, option.description).required(option.required),
-         );
+      for (locale, name) in &self.name_localizations {
+         created = created.name_localized(*locale, *name);
+      }
+      for (locale, description) in &self.description_localizations {
+         created = created.description_localized(*locale, *description);
       }
-      if let Err(err) = Command::create_global_command(ctx, created).await {
-         error!("Could not register command: {:?}", err)
+      for option in &self.options {
+         let mut created_option = CreateCommandOption::new(option.kind, option.name, option.description)
+            .required(option.required)
+            .set_autocomplete(option.autocomplete);
+         for (locale, name) in &option.name_localizations {
+            created_option = created_option.name_localized(*locale, *name);
+         }
+         for (locale, description) in &option.description_localizations {
+            created_option = created_option.description_localized(*locale, *description);
+         }
+         created = created.add_option(created_option);
       }
+      created
    }
 }
+
+/// Whether Discord has exactly the configured translations, where it may leave out the map when there are none.
+fn same_localizations(registered: &Option<HashMap<String, String>>, configured: &[(&str, &str)]) -> bool {
+   let registered = registered.as_ref();
+   registered.map_or(0, HashMap::len) == configured.len()
+      && configured.iter().all(|(locale, text)| {
+         registered
+            .and_then(|registered| registered.get(*locale))
+            .is_some_and(|registered| registered == text)
+      })
+}
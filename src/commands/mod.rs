@@ -1,14 +1,20 @@
-use config::{CommandConfig, CommandOption};
-use log::error;
+use config::{CommandConfig, CommandOption, Localizations};
+use log::{error, info};
 use serenity::{
    client::Context,
-   model::application::{Command, CommandOptionType},
+   model::{
+      application::{Command, CommandOptionType},
+      id::CommandId,
+   },
 };
+use std::fmt;
+
+use crate::locale::Locale;
 
 mod config;
 
-pub async fn create_or_update(ctx: &Context) {
-   let commands: Vec<CommandConfig> = vec![
+fn command_configs() -> Vec<CommandConfig<'static>> {
+   let configs = vec![
       CommandConfig {
          name: "help",
          description: "Display help information",
@@ -27,7 +33,33 @@ pub async fn create_or_update(ctx: &Context) {
             description: "the name of the sound file",
             kind: CommandOptionType::String,
             required: true,
+            autocomplete: true,
+            ..Default::default()
+         }],
+         ..Default::default()
+      },
+      CommandConfig {
+         name: "random",
+         description: "Play a random sound file from the library",
+         options: vec![CommandOption {
+            name: "filter",
+            description: "only pick sound files whose name contains this",
+            kind: CommandOptionType::String,
+            ..Default::default()
+         }],
+         ..Default::default()
+      },
+      CommandConfig {
+         name: "search",
+         description: "Find sound files by name or tag, even with typos",
+         options: vec![CommandOption {
+            name: "query",
+            description: "what to look for",
+            kind: CommandOptionType::String,
+            required: true,
+            ..Default::default()
          }],
+         ..Default::default()
       },
       CommandConfig {
          name: "youtube",
@@ -37,7 +69,97 @@ pub async fn create_or_update(ctx: &Context) {
             description: "the YouTube URL",
             kind: CommandOptionType::String,
             required: true,
+            ..Default::default()
          }],
+         ..Default::default()
+      },
+      CommandConfig {
+         name: "upload",
+         description: "Add a sound file to the library",
+         options: vec![
+            CommandOption {
+               name: "file",
+               description: "the MP3, OGG, Opus, WAV or FLAC file to upload",
+               kind: CommandOptionType::Attachment,
+               required: true,
+               ..Default::default()
+            },
+            CommandOption {
+               name: "name",
+               description: "the name to play the sound file by",
+               kind: CommandOptionType::String,
+               required: true,
+               ..Default::default()
+            },
+         ],
+         ..Default::default()
+      },
+      CommandConfig {
+         name: "trim",
+         description: "Create or replace a sound file from part of an existing one or an upload",
+         options: vec![
+            CommandOption {
+               name: "name",
+               description: "the sound file to write, which is also the one trimmed if no other source is given",
+               kind: CommandOptionType::String,
+               required: true,
+               autocomplete: true,
+               ..Default::default()
+            },
+            CommandOption {
+               name: "start",
+               description: "seconds into the source to start from",
+               kind: CommandOptionType::Number,
+               ..Default::default()
+            },
+            CommandOption {
+               name: "end",
+               description: "seconds into the source to stop at",
+               kind: CommandOptionType::Number,
+               ..Default::default()
+            },
+            CommandOption {
+               name: "strip_silence",
+               description: "also cut the silence from the beginning and end",
+               kind: CommandOptionType::Boolean,
+               ..Default::default()
+            },
+            CommandOption {
+               name: "clip",
+               description: "an existing sound file to trim",
+               kind: CommandOptionType::String,
+               autocomplete: true,
+               ..Default::default()
+            },
+            CommandOption {
+               name: "file",
+               description: "an MP3, OGG, Opus, WAV or FLAC file to trim",
+               kind: CommandOptionType::Attachment,
+               ..Default::default()
+            },
+         ],
+         ..Default::default()
+      },
+      CommandConfig {
+         name: "tags",
+         description: "Show or change the tags /search matches a sound file by",
+         options: vec![
+            CommandOption {
+               name: "clip",
+               description: "the sound file to tag",
+               kind: CommandOptionType::String,
+               required: true,
+               autocomplete: true,
+               ..Default::default()
+            },
+            CommandOption {
+               name: "tags",
+               description: "comma separated tags to replace the current ones, or - to remove them",
+               kind: CommandOptionType::String,
+               ..Default::default()
+            },
+         ],
+         ..Default::default()
       },
       CommandConfig {
          name: "summon",
@@ -49,16 +171,321 @@ pub async fn create_or_update(ctx: &Context) {
          description: "Stop the bot audio playback",
          ..Default::default()
       },
+      CommandConfig {
+         name: "volume",
+         description: "Show or change the playback volume",
+         options: vec![
+            CommandOption {
+               name: "level",
+               description: "the new volume as a percentage, from 0 to 200",
+               kind: CommandOptionType::Integer,
+               ..Default::default()
+            },
+            CommandOption {
+               name: "clip",
+               description: "a sound file to adjust relative to the server volume",
+               kind: CommandOptionType::String,
+               autocomplete: true,
+               ..Default::default()
+            },
+         ],
+         ..Default::default()
+      },
+      CommandConfig {
+         name: "token",
+         description: "Get a private token for playing sound files through the web server",
+         options: vec![CommandOption {
+            name: "revoke",
+            description: "revoke your token instead of replacing it",
+            kind: CommandOptionType::Boolean,
+            ..Default::default()
+         }],
+         ..Default::default()
+      },
+      CommandConfig {
+         name: "queue",
+         description: "Show the sound files waiting to be played",
+         ..Default::default()
+      },
+      CommandConfig {
+         name: "stats",
+         description: "Show the most played sound files, the members who play the most and recent plays",
+         ..Default::default()
+      },
+      CommandConfig {
+         name: "skip",
+         description: "Skip to the next sound file in the queue",
+         ..Default::default()
+      },
    ];
+   configs.into_iter().map(localize).collect()
+}
+
+/// Adds the catalogs' translations of the command's name and description, and those of its options.
+fn localize(mut config: CommandConfig<'static>) -> CommandConfig<'static> {
+   let key = format!("command.{}", config.name);
+   config.name_localizations = translations(&format!("{key}.name"));
+   config.description_localizations = translations(&format!("{key}.description"));
+   for option in &mut config.options {
+      option.name_localizations = translations(&format!("{key}.{}.name", option.name));
+      option.description_localizations = translations(&format!("{key}.{}.description", option.name));
+   }
+   config
+}
+
+fn translations(key: &str) -> Localizations<'static> {
+   Locale::ALL
+      .into_iter()
+      .filter(|locale| *locale != Locale::English)
+      .filter_map(|locale| Some((locale.code(), locale.lookup(key)?)))
+      .collect()
+}
+
+/// The names of every command the bot registers, without the leading slash.
+pub fn names() -> Vec<&'static str> {
+   command_configs().iter().map(|config| config.name).collect()
+}
+
+/// A change that brings the registered commands in line with `command_configs`.
+#[derive(Debug, PartialEq)]
+enum Change<'a> {
+   Create(&'a CommandConfig<'a>),
+   Edit(CommandId, &'a CommandConfig<'a>),
+   Delete(CommandId, &'a str),
+}
+
+impl fmt::Display for Change<'_> {
+   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+      match self {
+         Change::Create(config) => write!(f, "create /{}", config.name),
+         Change::Edit(_, config) => write!(f, "update /{}", config.name),
+         Change::Delete(_, name) => write!(f, "delete /{name}"),
+      }
+   }
+}
+
+/// Registers new commands, updates changed ones and deletes those the bot no longer has, either globally or, in
+/// development, just in the configured test guild. In a dry run the changes are only logged.
+pub async fn sync(ctx: &Context) {
+   let config = crate::config::get(ctx).await;
+   let scope = match config.dev_guild_id {
+      Some(guild_id) => format!("guild {guild_id}"),
+      None => "global".to_string(),
+   };
+   let registered = match config.dev_guild_id {
+      Some(guild_id) => guild_id.get_commands_with_localizations(ctx).await,
+      None => Command::get_global_commands_with_localizations(ctx).await,
+   };
+   let registered = match registered {
+      Ok(registered) => registered,
+      Err(err) => {
+         error!(
+            "Could not load current {} commands from Discord, no changes will be made: {:?}",
+            scope, err
+         );
+         return;
+      }
+   };
+
+   let configs = command_configs();
+   let changes = plan(&configs, &registered);
+   if changes.is_empty() {
+      info!("All {} commands are up to date", scope);
+   }
+   for change in changes {
+      if config.commands_dry_run {
+         info!("Dry run, would {} ({} commands)", change, scope);
+         continue;
+      }
+      let result = match (&change, config.dev_guild_id) {
+         (Change::Create(command), Some(guild_id)) => guild_id.create_command(ctx, command.builder()).await.map(|_| ()),
+         (Change::Create(command), None) => Command::create_global_command(ctx, command.builder()).await.map(|_| ()),
+         (Change::Edit(id, command), Some(guild_id)) => {
+            guild_id.edit_command(ctx, *id, command.builder()).await.map(|_| ())
+         }
+         (Change::Edit(id, command), None) => Command::edit_global_command(ctx, *id, command.builder())
+            .await
+            .map(|_| ()),
+         (Change::Delete(id, _), Some(guild_id)) => guild_id.delete_command(ctx, *id).await,
+         (Change::Delete(id, _), None) => Command::delete_global_command(ctx, *id).await,
+      };
+      match result {
+         Ok(_) => info!("Synced {} commands: {}", scope, change),
+         Err(err) => error!("Could not {} ({} commands): {:?}", change, scope, err),
+      }
+   }
+}
+
+/// Works out what has to change for the registered commands to match the configured ones.
+fn plan<'a>(configs: &'a [CommandConfig<'a>], registered: &'a [Command]) -> Vec<Change<'a>> {
+   let mut changes = Vec::new();
+   for config in configs {
+      match registered.iter().find(|command| command.name == config.name) {
+         Some(command) if config.is_equivalent(command) => (),
+         Some(command) => changes.push(Change::Edit(command.id, config)),
+         None => changes.push(Change::Create(config)),
+      }
+   }
+   for command in registered {
+      if !configs.iter().any(|config| config.name == command.name) {
+         changes.push(Change::Delete(command.id, &command.name));
+      }
+   }
+   changes
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   fn registered(id: u64, name: &str, description: &str) -> Command {
+      serde_json::from_value(serde_json::json!({
+         "id": id.to_string(),
+         "type": 1,
+         "application_id": "1",
+         "name": name,
+         "description": description,
+         "version": "1",
+      }))
+      .unwrap()
+   }
+
+   #[test]
+   fn test_sync_planned() {
+      let configs = vec![
+         CommandConfig {
+            name: "help",
+            description: "Display help information",
+            ..Default::default()
+         },
+         CommandConfig {
+            name: "list",
+            description: "List available sound files",
+            ..Default::default()
+         },
+         CommandConfig {
+            name: "stats",
+            description: "Show the most played sound files",
+            ..Default::default()
+         },
+      ];
+      let registered = vec![
+         registered(10, "help", "Display help information"),
+         registered(11, "list", "List the sound files"),
+         registered(12, "removed", "A command the bot no longer has"),
+      ];
+
+      assert_eq!(
+         plan(&configs, &registered),
+         [
+            Change::Edit(CommandId::new(11), &configs[1]),
+            Change::Create(&configs[2]),
+            Change::Delete(CommandId::new(12), "removed"),
+         ]
+      );
+      assert!(plan(&configs[..1], &registered[..1]).is_empty());
+   }
+
+   #[test]
+   fn test_reordered_options_updated() {
+      let option = |name| CommandOption {
+         name,
+         description: name,
+         ..Default::default()
+      };
+      let config = CommandConfig {
+         name: "trim",
+         description: "Trim a clip",
+         options: vec![option("name"), option("start")],
+         ..Default::default()
+      };
+      let command: Command = serde_json::from_value(serde_json::json!({
+         "id": "1",
+         "type": 1,
+         "application_id": "1",
+         "name": "trim",
+         "description": "Trim a clip",
+         "version": "1",
+         "options": [
+            { "type": 3, "name": "start", "description": "start" },
+            { "type": 3, "name": "name", "description": "name" },
+         ],
+      }))
+      .unwrap();
+
+      assert!(!config.is_equivalent(&command));
+      assert!(CommandConfig {
+         options: vec![option("start"), option("name")],
+         ..config
+      }
+      .is_equivalent(&command));
+   }
+
+   #[test]
+   fn test_translations_updated() {
+      let config = CommandConfig {
+         name: "stop",
+         description: "Stop the bot audio playback",
+         description_localizations: vec![("de", "Die Wiedergabe des Bots stoppen")],
+         ..Default::default()
+      };
+      let command = |localizations| -> Command {
+         serde_json::from_value(serde_json::json!({
+            "id": "1",
+            "type": 1,
+            "application_id": "1",
+            "name": "stop",
+            "description": "Stop the bot audio playback",
+            "description_localizations": localizations,
+            "version": "1",
+         }))
+         .unwrap()
+      };
+
+      assert!(config.is_equivalent(&command(serde_json::json!({ "de": "Die Wiedergabe des Bots stoppen" }))));
+      assert!(!config.is_equivalent(&command(serde_json::json!({ "de": "Wiedergabe stoppen" }))));
+      assert!(!config.is_equivalent(&command(serde_json::Value::Null)));
+      assert!(CommandConfig {
+         description_localizations: Vec::new(),
+         ..config
+      }
+      .is_equivalent(&command(serde_json::Value::Null)));
+   }
+
+   #[test]
+   fn test_every_command_translated() {
+      let configs = command_configs();
+      let mut keys = Vec::new();
+      for config in &configs {
+         let descriptions = config
+            .options
+            .iter()
+            .map(|option| (option.name, &option.description_localizations))
+            .chain([(config.name, &config.description_localizations)]);
+         for (name, localizations) in descriptions {
+            assert_eq!(localizations.len(), Locale::ALL.len() - 1, "{name} is not translated");
+            // Discord's limit on the length of a description
+            assert!(localizations
+               .iter()
+               .all(|(_, description)| description.chars().count() <= 100));
+         }
+         keys.push(format!("command.{}.description", config.name));
+         keys.extend(
+            config
+               .options
+               .iter()
+               .map(|option| format!("command.{}.{}.description", config.name, option.name)),
+         );
+      }
 
-   if let Ok(current_commands) = Command::get_global_commands(ctx).await {
-      for config in commands {
-         match current_commands.iter().find(|command| command.name == config.name) {
-            Some(command) if !config.is_equivalent(command) => (),
-            _ => config.register_command(ctx).await,
+      // a translation of a command the bot doesn't have is most likely a typo
+      for locale in Locale::ALL {
+         for (key, _) in locale.catalog() {
+            assert!(
+               !key.starts_with("command.") || keys.iter().any(|known| known == key),
+               "{locale:?} translates {key}, which is not a command"
+            );
          }
       }
-   } else {
-      error!("Could not load current commands from Discord, no changes will be made");
    }
 }
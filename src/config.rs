@@ -0,0 +1,306 @@
+use reqwest::Url;
+use serde::Deserialize;
+use serenity::{
+   client::Context,
+   model::id::{ApplicationId, GuildId},
+   prelude::TypeMapKey,
+};
+use std::{
+   env, fs,
+   path::{Path, PathBuf},
+   sync::Arc,
+   time::Duration,
+};
+
+const USAGE: &str = "Usage: my_man [--config <file>] [--discord-token <token>] [--application-id <id>] \
+                     [--audio-file-dir <dir>] [--web-uri <uri>] [--shutdown-grace <duration>] \
+                     [--dev-guild-id <id>] [--commands-dry-run <true|false>]";
+
+// The environment variable and command line flag each setting can be given by, in the order `Settings` lists them
+const SOURCES: [(&str, &str); 7] = [
+   ("DISCORD_TOKEN", "--discord-token"),
+   ("APPLICATION_ID", "--application-id"),
+   ("AUDIO_FILE_DIR", "--audio-file-dir"),
+   ("WEB_URI", "--web-uri"),
+   ("SHUTDOWN_GRACE", "--shutdown-grace"),
+   ("DEV_GUILD_ID", "--dev-guild-id"),
+   ("COMMANDS_DRY_RUN", "--commands-dry-run"),
+];
+
+/// Settings for the whole bot, read and checked once at startup.
+///
+/// Each setting comes from the first of the command line flags, the environment and the config file to give it.
+#[derive(Clone)]
+pub struct Config {
+   pub discord_token: String,
+   pub application_id: ApplicationId,
+   /// Directory holding a folder of clips for each guild
+   pub audio_file_dir: PathBuf,
+   /// Public base URI of the web server, without a trailing slash
+   pub web_uri: String,
+   /// How long playing tracks get to finish once the bot is asked to shut down
+   pub shutdown_grace: Duration,
+   /// Register commands to just this guild, where changes show up immediately, instead of globally
+   pub dev_guild_id: Option<GuildId>,
+   /// Only log the changes that syncing commands would make
+   pub commands_dry_run: bool,
+}
+
+impl TypeMapKey for Config {
+   type Value = Arc<Config>;
+}
+
+impl Config {
+   /// Reads the command line, the environment and the config file named by `--config` or `CONFIG_FILE`, listing
+   /// every problem found if they don't make a usable configuration.
+   pub fn load() -> Result<Self, Vec<String>> {
+      let (flags, file) = Settings::from_args(env::args().skip(1)).map_err(|problem| vec![problem])?;
+      let from_file = match file.or_else(|| env::var_os("CONFIG_FILE").map(PathBuf::from)) {
+         Some(path) => Settings::read(&path).map_err(|problem| vec![problem])?,
+         None => Settings::default(),
+      };
+      flags.or(Settings::from_env()).or(from_file).validate()
+   }
+}
+
+pub async fn get(ctx: &Context) -> Arc<Config> {
+   ctx.data
+      .read()
+      .await
+      .get::<Config>()
+      .cloned()
+      .expect("Config should have been placed during initialization")
+}
+
+/// The settings a single source gives, before they are checked.
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+struct Settings {
+   discord_token: Option<String>,
+   application_id: Option<String>,
+   audio_file_dir: Option<String>,
+   web_uri: Option<String>,
+   shutdown_grace: Option<String>,
+   dev_guild_id: Option<String>,
+   commands_dry_run: Option<String>,
+}
+
+impl Settings {
+   fn values_mut(&mut self) -> [&mut Option<String>; 7] {
+      [
+         &mut self.discord_token,
+         &mut self.application_id,
+         &mut self.audio_file_dir,
+         &mut self.web_uri,
+         &mut self.shutdown_grace,
+         &mut self.dev_guild_id,
+         &mut self.commands_dry_run,
+      ]
+   }
+
+   fn read(path: &Path) -> Result<Self, String> {
+      let content = fs::read_to_string(path).map_err(|err| format!("Could not read {}: {err}", path.display()))?;
+      toml::from_str(&content).map_err(|err| format!("Invalid config file {}: {err}", path.display()))
+   }
+
+   fn from_env() -> Self {
+      let mut settings = Self::default();
+      for ((variable, _), value) in SOURCES.iter().zip(settings.values_mut()) {
+         *value = env::var(variable).ok();
+      }
+      settings
+   }
+
+   /// Parses `--flag value` or `--flag=value` pairs, along with the config file given by `--config` if any.
+   fn from_args<I: IntoIterator<Item = String>>(args: I) -> Result<(Self, Option<PathBuf>), String> {
+      let mut settings = Self::default();
+      let mut file = None;
+      let mut args = args.into_iter();
+      while let Some(arg) = args.next() {
+         let (flag, value) = match arg.split_once('=') {
+            Some((flag, value)) => (flag.to_owned(), Some(value.to_owned())),
+            None => (arg, None),
+         };
+         if flag == "--help" || flag == "-h" {
+            return Err(USAGE.to_string());
+         }
+         let value = value
+            .or_else(|| args.next())
+            .ok_or_else(|| format!("{flag} needs a value\n{USAGE}"))?;
+         if flag == "--config" {
+            file = Some(PathBuf::from(value));
+            continue;
+         }
+         match SOURCES.iter().position(|(_, name)| *name == flag) {
+            Some(index) => *settings.values_mut()[index] = Some(value),
+            None => return Err(format!("Unknown flag {flag}\n{USAGE}")),
+         }
+      }
+      Ok((settings, file))
+   }
+
+   /// Fills in whatever these settings leave out from the fallback.
+   fn or(mut self, mut fallback: Self) -> Self {
+      for (value, fallback) in self.values_mut().into_iter().zip(fallback.values_mut()) {
+         if value.is_none() {
+            *value = fallback.take();
+         }
+      }
+      self
+   }
+
+   fn validate(self) -> Result<Config, Vec<String>> {
+      let mut problems = Vec::new();
+      let mut require = |value: Option<String>, (variable, flag): (&str, &str)| {
+         if value.is_none() {
+            problems.push(format!(
+               "{variable} must be set, either in the environment, with {flag} or as `{}` in the config file",
+               variable.to_lowercase()
+            ));
+         }
+         value.unwrap_or_default()
+      };
+      let discord_token = require(self.discord_token, SOURCES[0]);
+      let application_id = require(self.application_id, SOURCES[1]);
+      let audio_file_dir = require(self.audio_file_dir, SOURCES[2]);
+      let web_uri = require(self.web_uri, SOURCES[3]);
+
+      let parsed_id = application_id.parse::<u64>().ok().filter(|id| *id != 0);
+      if parsed_id.is_none() && !application_id.is_empty() {
+         problems.push(format!("APPLICATION_ID must be a numeric ID, not {application_id}"));
+      }
+      let audio_file_dir = PathBuf::from(audio_file_dir);
+      if !audio_file_dir.as_os_str().is_empty() && !audio_file_dir.is_dir() {
+         problems.push(format!(
+            "AUDIO_FILE_DIR {} is not a directory",
+            audio_file_dir.display()
+         ));
+      }
+      if !web_uri.is_empty() && !Url::parse(&web_uri).is_ok_and(|uri| ["http", "https"].contains(&uri.scheme())) {
+         problems.push(format!("WEB_URI must be an http or https URI, not {web_uri}"));
+      }
+      let shutdown_grace = match self.shutdown_grace {
+         Some(grace) => humantime::parse_duration(&grace).unwrap_or_else(|_| {
+            problems.push(format!("SHUTDOWN_GRACE must be a duration, like 10s, not {grace}"));
+            Duration::ZERO
+         }),
+         None => Duration::ZERO,
+      };
+      let dev_guild_id = self.dev_guild_id.and_then(|id| match id.parse::<u64>() {
+         Ok(id) if id != 0 => Some(GuildId::new(id)),
+         _ => {
+            problems.push(format!("DEV_GUILD_ID must be a numeric ID, not {id}"));
+            None
+         }
+      });
+      let commands_dry_run = match self.commands_dry_run.as_deref() {
+         None | Some("false") => false,
+         Some("true") => true,
+         Some(other) => {
+            problems.push(format!("COMMANDS_DRY_RUN must be true or false, not {other}"));
+            false
+         }
+      };
+
+      match parsed_id {
+         Some(application_id) if problems.is_empty() => Ok(Config {
+            discord_token,
+            application_id: ApplicationId::new(application_id),
+            audio_file_dir,
+            web_uri: web_uri.trim_end_matches('/').to_string(),
+            shutdown_grace,
+            dev_guild_id,
+            commands_dry_run,
+         }),
+         _ => Err(problems),
+      }
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+   use tempfile::tempdir;
+
+   fn args(args: &[&str]) -> Vec<String> {
+      args.iter().map(|arg| arg.to_string()).collect()
+   }
+
+   #[test]
+   fn test_flags_parsed() {
+      let (settings, file) = Settings::from_args(args(&[
+         "--config",
+         "my_man.toml",
+         "--application-id=42",
+         "--web-uri",
+         "https://example.com",
+      ]))
+      .unwrap();
+
+      assert_eq!(file, Some(PathBuf::from("my_man.toml")));
+      assert_eq!(settings.application_id.as_deref(), Some("42"));
+      assert_eq!(settings.web_uri.as_deref(), Some("https://example.com"));
+      assert!(Settings::from_args(args(&["--volume", "11"])).is_err());
+      assert!(Settings::from_args(args(&["--web-uri"])).is_err());
+   }
+
+   #[test]
+   fn test_earlier_sources_take_precedence() {
+      let flags = Settings {
+         web_uri: Some("https://flag.example.com".to_string()),
+         ..Default::default()
+      };
+      let from_env = Settings {
+         web_uri: Some("https://env.example.com".to_string()),
+         discord_token: Some("env token".to_string()),
+         ..Default::default()
+      };
+      let from_file: Settings = toml::from_str("discord_token = \"file token\"\napplication_id = \"42\"").unwrap();
+
+      let settings = flags.or(from_env).or(from_file);
+      assert_eq!(settings.web_uri.as_deref(), Some("https://flag.example.com"));
+      assert_eq!(settings.discord_token.as_deref(), Some("env token"));
+      assert_eq!(settings.application_id.as_deref(), Some("42"));
+   }
+
+   #[test]
+   fn test_valid_settings_accepted() {
+      let dir = tempdir().unwrap();
+      let config = Settings {
+         discord_token: Some("token".to_string()),
+         application_id: Some("42".to_string()),
+         audio_file_dir: Some(dir.path().to_string_lossy().into_owned()),
+         web_uri: Some("https://example.com/".to_string()),
+         shutdown_grace: Some("10s".to_string()),
+         dev_guild_id: Some("7".to_string()),
+         commands_dry_run: Some("true".to_string()),
+      }
+      .validate()
+      .unwrap();
+
+      assert_eq!(config.application_id, ApplicationId::new(42));
+      assert_eq!(config.web_uri, "https://example.com");
+      assert_eq!(config.shutdown_grace, Duration::from_secs(10));
+      assert_eq!(config.dev_guild_id, Some(GuildId::new(7)));
+      assert!(config.commands_dry_run);
+   }
+
+   #[test]
+   fn test_every_problem_reported() {
+      let problems = Settings {
+         discord_token: None,
+         application_id: Some("my_man".to_string()),
+         audio_file_dir: Some("/definitely/not/here".to_string()),
+         web_uri: Some("example.com".to_string()),
+         shutdown_grace: Some("soon".to_string()),
+         dev_guild_id: Some("test".to_string()),
+         commands_dry_run: Some("yes".to_string()),
+      }
+      .validate()
+      .err()
+      .unwrap();
+
+      assert_eq!(problems.len(), 7);
+      assert!(problems[0].starts_with("DISCORD_TOKEN must be set"));
+   }
+}
@@ -0,0 +1,247 @@
+use rocket::{
+   form::{Form, FromForm},
+   get,
+   http::{Cookie, CookieJar, SameSite, Status},
+   post,
+   response::{content::RawHtml, Redirect},
+   State,
+};
+use std::sync::Arc;
+
+use crate::{
+   api_tokens::{ApiUser, TOKEN_COOKIE},
+   audio::{audio_source::ClipInfo, clip_index},
+   config::Config,
+   guilds::get_guild,
+   http::{escape_html, guild_clips},
+   WebContext,
+};
+
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 1rem; background: #1e1f22; color: #dbdee1; }
+header { display: flex; flex-wrap: wrap; gap: .5rem; align-items: center; }
+header h1 { flex: 1 0 100%; margin: 0 0 .5rem; }
+header form { margin-left: auto; }
+input, button { font: inherit; padding: .4rem .6rem; border-radius: .3rem; border: 1px solid #4e5058; }
+input { background: #383a40; color: inherit; min-width: 16rem; }
+button { background: #4e5058; color: inherit; cursor: pointer; }
+#stop { background: #da373c; border-color: #da373c; }
+#playing { font-weight: bold; }
+#status:empty, #playing:empty { display: none; }
+#clips { display: grid; grid-template-columns: repeat(auto-fill, minmax(9rem, 1fr)); gap: .5rem; }
+.clip { min-height: 3.5rem; overflow-wrap: anywhere; }
+.clip.playing { background: #248046; border-color: #248046; }
+";
+
+// Plays and stops through the same routes as the API, and polls what is playing so the page can stay open
+const SCRIPT: &str = "
+const base = document.body.dataset.base;
+const guild = document.body.dataset.guild;
+const clips = document.getElementById('clips');
+const status = document.getElementById('status');
+const playing = document.getElementById('playing');
+let playingNames = [];
+
+function show(message) {
+   status.textContent = message;
+   clearTimeout(show.timeout);
+   show.timeout = setTimeout(() => status.textContent = '', 5000);
+}
+
+function explain(response, name) {
+   switch (response.status) {
+      case 401: location.reload(); return '';
+      case 403: return 'Only Sound Clip Admins can do that in this server';
+      case 404: return name + ' no longer exists';
+      case 409: return 'Join a voice channel in this server, or the one the bot is in, first';
+      case 429: return 'You are playing clips too quickly, try again in ' + response.headers.get('Retry-After') + 's';
+      case 503: return 'The bot cannot join your channel right now, try again soon';
+      default: return 'Something went wrong';
+   }
+}
+
+async function post(path, name) {
+   const response = await fetch(base + path, { method: 'POST', credentials: 'same-origin' });
+   if (response.ok) {
+      refreshPlaying();
+   } else {
+      show(explain(response, name));
+   }
+}
+
+function render(names) {
+   clips.replaceChildren(...names.map(name => {
+      const button = document.createElement('button');
+      button.className = 'clip';
+      button.dataset.name = name;
+      button.textContent = name;
+      return button;
+   }));
+   markPlaying();
+}
+
+function markPlaying() {
+   for (const button of clips.children) {
+      button.classList.toggle('playing', playingNames.includes(button.dataset.name));
+   }
+}
+
+async function refreshPlaying() {
+   const response = await fetch(base + '/playing/' + guild, { credentials: 'same-origin' });
+   if (response.ok) {
+      playingNames = await response.json();
+      playing.textContent = playingNames.length ? 'Now playing: ' + playingNames.join(', ') : '';
+      markPlaying();
+   }
+}
+
+let search;
+document.getElementById('search').addEventListener('input', event => {
+   clearTimeout(search);
+   search = setTimeout(async () => {
+      const query = encodeURIComponent(event.target.value.trim());
+      const response = await fetch(base + '/clips/' + guild + '?q=' + query, { headers: { Accept: 'application/json' } });
+      if (response.ok) {
+         render((await response.json()).map(clip => clip.name));
+      }
+   }, 200);
+});
+clips.addEventListener('click', event => {
+   const name = event.target.dataset.name;
+   if (name) {
+      post('/play/' + guild + '/' + encodeURIComponent(name), name);
+   }
+});
+document.getElementById('stop').addEventListener('click', () => post('/stop/' + guild, ''));
+
+refreshPlaying();
+setInterval(() => document.hidden || refreshPlaying(), 2000);
+";
+
+#[derive(FromForm)]
+pub struct Login<'r> {
+   token: &'r str,
+}
+
+/// A grid of the guild's clips that play in the voice channel of whoever is logged in, or a login form if nobody is.
+#[get("/dashboard/<guild_id>")]
+pub async fn page(
+   ctx: &State<WebContext>, config: &State<Arc<Config>>, user: Option<ApiUser>, guild_id: u64,
+) -> Result<RawHtml<String>, Status> {
+   if user.is_none() {
+      let guild = get_guild(&ctx.cache, guild_id).ok_or(Status::NotFound)?;
+      return Ok(login_page(&config.web_uri, &guild.name, guild_id, None));
+   }
+   let (guild_name, clips) = guild_clips(ctx, guild_id, None).await?;
+   let buttons = clips.iter().map(clip_button).collect::<String>();
+   let title = escape_html(&guild_name);
+   let base = escape_html(&config.web_uri);
+
+   Ok(RawHtml(format!(
+      "<!DOCTYPE html>
+<html>
+<head><meta charset=\"utf-8\"><meta name=\"viewport\" content=\"width=device-width, initial-scale=1\"><title>{title} soundboard</title><style>{STYLE}</style></head>
+<body data-base=\"{base}\" data-guild=\"{guild_id}\">
+<header>
+<h1>{title}</h1>
+<input id=\"search\" type=\"search\" placeholder=\"Search names and tags\" autofocus>
+<button id=\"stop\">Stop</button>
+<form method=\"post\" action=\"{base}/dashboard/{guild_id}/logout\"><button>Log out</button></form>
+</header>
+<p id=\"playing\"></p>
+<p id=\"status\" role=\"status\"></p>
+<div id=\"clips\">{buttons}</div>
+<script>{SCRIPT}</script>
+</body>
+</html>"
+   )))
+}
+
+/// Logs in with a token from `/token`, which is kept in a cookie until the user logs out or the token is replaced.
+#[post("/dashboard/<guild_id>/login", data = "<login>")]
+pub async fn login(
+   ctx: &State<WebContext>, config: &State<Arc<Config>>, cookies: &CookieJar<'_>, guild_id: u64, login: Form<Login<'_>>,
+) -> Result<Redirect, (Status, RawHtml<String>)> {
+   let token = login.token.trim();
+   if ctx.tokens.owner(token).await.is_none() {
+      let guild_name = get_guild(&ctx.cache, guild_id)
+         .map(|guild| guild.name)
+         .unwrap_or_default();
+      let problem = "That token doesn't work, run /token in Discord for a new one";
+      return Err((
+         Status::Unauthorized,
+         login_page(&config.web_uri, &guild_name, guild_id, Some(problem)),
+      ));
+   }
+
+   cookies.add(
+      Cookie::build((TOKEN_COOKIE, token.to_owned()))
+         .same_site(SameSite::Strict)
+         .secure(config.web_uri.starts_with("https:"))
+         .permanent(),
+   );
+   Ok(Redirect::to(format!("{}/dashboard/{guild_id}", config.web_uri)))
+}
+
+#[post("/dashboard/<guild_id>/logout")]
+pub fn logout(config: &State<Arc<Config>>, cookies: &CookieJar<'_>, guild_id: u64) -> Redirect {
+   cookies.remove(TOKEN_COOKIE);
+   Redirect::to(format!("{}/dashboard/{guild_id}", config.web_uri))
+}
+
+fn login_page(web_uri: &str, guild_name: &str, guild_id: u64, problem: Option<&str>) -> RawHtml<String> {
+   let title = escape_html(guild_name);
+   let base = escape_html(web_uri);
+   let problem = problem
+      .map(|problem| format!("<p role=\"alert\">{}</p>", escape_html(problem)))
+      .unwrap_or_default();
+
+   RawHtml(format!(
+      "<!DOCTYPE html>
+<html>
+<head><meta charset=\"utf-8\"><meta name=\"viewport\" content=\"width=device-width, initial-scale=1\"><title>{title} soundboard</title><style>{STYLE}</style></head>
+<body>
+<h1>{title}</h1>
+<p>Run <code>/token</code> in Discord and paste the token here to play clips from this page.</p>
+{problem}
+<form method=\"post\" action=\"{base}/dashboard/{guild_id}/login\"><input type=\"password\" name=\"token\" placeholder=\"API token\" required autofocus> <button>Log in</button></form>
+</body>
+</html>"
+   ))
+}
+
+fn clip_button(clip: &ClipInfo) -> String {
+   let name = escape_html(&clip.name);
+   let details = [
+      clip.details.duration_ms.map(clip_index::format_duration),
+      Some(clip.tags.join(", ")).filter(|tags| !tags.is_empty()),
+   ]
+   .into_iter()
+   .flatten()
+   .collect::<Vec<_>>()
+   .join(" · ");
+   format!(
+      "<button class=\"clip\" data-name=\"{name}\" title=\"{}\">{name}</button>",
+      escape_html(&details)
+   )
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn test_clip_buttons_escaped() {
+      let clip = ClipInfo {
+         name: "<b>\"loud\"</b>".to_string(),
+         tags: vec!["funny".to_string(), "loud".to_string()],
+         ..Default::default()
+      };
+
+      assert_eq!(
+         clip_button(&clip),
+         "<button class=\"clip\" data-name=\"&lt;b&gt;&quot;loud&quot;&lt;/b&gt;\" title=\"funny, loud\">\
+          &lt;b&gt;&quot;loud&quot;&lt;/b&gt;</button>"
+      );
+   }
+}
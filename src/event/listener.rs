@@ -1,5 +1,3 @@
-use std::{env, path::PathBuf};
-
 use log::{error, info};
 use reqwest::Client;
 use serenity::{
@@ -15,7 +13,17 @@ use serenity::{
    },
 };
 
-use crate::{actions, audio::playback, call_result, chat, commands, event::util, role};
+use crate::{
+   actions,
+   audio::{audio_source, playback},
+   autocomplete,
+   call_result::{CallError, CallResult},
+   chat, commands,
+   event::util,
+   guild_config,
+   locale::{Locale, Message},
+   permissions, role,
+};
 
 pub struct SoundboardListener {
    client: Client,
@@ -27,92 +35,175 @@ impl SoundboardListener {
    }
 }
 
-static HELP_MSG: &str = "You can type any of the following commands:
-```
-/list    - Returns a list of available sound files.
-/play    - Plays the specified sound from the list.
-/youtube - Plays the youtube link specified.
-/stop    - Stops the currently playing sound(s).
-/summon  - Summon the bot to your current voice channel.
-```";
-
 #[async_trait::async_trait]
 impl EventHandler for SoundboardListener {
    async fn ready(&self, ctx: Context, ready: Ready) {
       info!("{} is connected!", ready.user.name);
       ctx.set_activity(Some(ActivityData::listening("commands: /help")));
-      commands::create_or_update(&ctx).await;
+      commands::sync(&ctx).await;
    }
 
    // Fired the first time the API sends data for a guild, even if it's not actually being created.
    // This should result in this event firing when the bot joins a new guild, or on bot startup.
    async fn guild_create(&self, ctx: Context, guild: Guild, _is_new: Option<bool>) {
-      let file_dir = env::var("AUDIO_FILE_DIR").expect("Audio file directory must be in the environment!");
-      let path: PathBuf = [file_dir, Into::<u64>::into(guild.id).to_string()].iter().collect();
+      let path = audio_source::guild_dir(&guild.id);
 
       match std::fs::create_dir_all(&path) {
-         Ok(_) => role::create_admin_role(&ctx, &guild.id, path).await,
+         Ok(_) => {
+            role::create_admin_role(&ctx, &guild.id, path).await;
+            if let Err(problems) = guild_config::get(&ctx).await.reload(&guild).await {
+               guild_config::report_problems(&ctx, &guild, &problems).await;
+            }
+         }
          Err(err) => error!("Could not generate clip directory for {}: {:?}", guild.id, err),
       }
    }
 
    async fn voice_state_update(&self, ctx: Context, old: Option<VoiceState>, new: VoiceState) {
-      match new.channel_id {
-         Some(channel_id)
-            if util::moved_to_non_afk(&ctx, new.guild_id.unwrap(), channel_id, old.and_then(|o| o.channel_id)) =>
+      let old_channel_id = old.and_then(|o| o.channel_id);
+      let Some(guild_id) = new.guild_id else {
+         return util::move_if_last_user(ctx, None).await;
+      };
+      let config = guild_config::get(&ctx).await.get(guild_id).await;
+      let left_channel_id = old_channel_id.filter(|id| new.channel_id != Some(*id));
+      match (left_channel_id, new.channel_id) {
+         // leaving, or moving away from, a channel the bot is playing to says goodbye there rather than following the
+         // member, since the bot can only be in one channel of a guild
+         (Some(left_channel_id), _)
+            if config.exit_sounds && util::bot_in_occupied_channel(&ctx, guild_id, left_channel_id) =>
          {
-            let msg = call_result::log_error_if_any(
-               playback::play_entrance(ctx, new.guild_id.unwrap(), channel_id, new.user_id).await,
-            )
-            .user_message;
+            let msg = user_message(
+               playback::play_exit(ctx, guild_id, left_channel_id, new.user_id).await,
+               Locale::default(),
+            );
+            info!("{}", msg);
+         }
+         (_, Some(channel_id)) if util::moved_to_non_ignored(&ctx, &config, guild_id, channel_id, old_channel_id) => {
+            let msg = user_message(
+               playback::play_entrance(ctx, guild_id, channel_id, new.user_id).await,
+               Locale::default(),
+            );
             info!("{}", msg);
          }
-         _ => util::move_if_last_user(ctx, new.guild_id).await,
+         _ => util::move_if_last_user(ctx, Some(guild_id)).await,
       }
    }
 
    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
-      if let Interaction::Command(command) = interaction {
+      if let Interaction::Autocomplete(autocomplete) = interaction {
+         autocomplete::respond(&ctx, &autocomplete).await;
+      } else if let Interaction::Command(command) = interaction {
          // create an initial placeholder result that shows the bot as "thinking"
-         let create_response = command
-            .create_response(
-               &ctx,
-               CreateInteractionResponse::Defer(
-                  CreateInteractionResponseMessage::new().flags(InteractionResponseFlags::EPHEMERAL),
-               ),
-            )
-            .await;
-
-         if let Err(msg) = create_response {
+         if let Err(msg) = command.create_response(&ctx, deferred_response()).await {
             error!("Could not respond to command: {:?}", msg);
             return;
          }
 
-         let result = match command.data.name.as_str() {
-            "play" => actions::play(&ctx, &command).await,
-            "youtube" => actions::youtube(&ctx, self.client.clone(), &command).await,
-            "help" => HELP_MSG.to_string(),
-            "list" => chat::list(&ctx, command.guild_id, &command.user).await,
-            "stop" => actions::stop(&ctx, &command).await,
-            "summon" => actions::summon(&ctx, &command).await,
-            _ => "Unrecognized command!".to_string(),
+         let locale = Locale::from_discord(&command.locale);
+         let mut components = Vec::new();
+         let result = match permissions::check(&ctx, &command).await {
+            Err(denied) => denied.text(locale),
+            Ok(_) => user_message(
+               match command.data.name.as_str() {
+                  "play" => actions::play(&ctx, &command).await,
+                  "random" => actions::random(&ctx, &command).await,
+                  "search" => {
+                     let (result, buttons) = actions::search(&command).await;
+                     components = buttons;
+                     result
+                  }
+                  "youtube" => actions::youtube(&ctx, self.client.clone(), &command).await,
+                  "help" => Ok(Message::new("help")),
+                  "list" => Ok(chat::list(&ctx, command.guild_id, &command.user).await),
+                  "stop" => actions::stop(&ctx, &command).await,
+                  "queue" => actions::queue(&ctx, &command).await,
+                  "volume" => actions::volume(&ctx, &command).await,
+                  "skip" => actions::skip(&ctx, &command).await,
+                  "stats" => actions::stats(&command).await,
+                  "summon" => actions::summon(&ctx, &command).await,
+                  "upload" => actions::upload(self.client.clone(), &command).await,
+                  "trim" => actions::trim(self.client.clone(), &command).await,
+                  "tags" => actions::tags(&command).await,
+                  "token" => actions::token(&ctx, &command).await,
+                  _ => Err(CallError::Invalid(Message::new("unrecognized_command"))),
+               },
+               locale,
+            ),
          };
 
          // update the response with the actual result of the action
          let edit_response = command
             .edit_response(
                &ctx,
-               EditInteractionResponse::new().add_embed(
-                  CreateEmbed::new()
-                     .colour(Color::FABLED_PINK)
-                     .title(format!("/{} result", command.data.name))
-                     .description(result),
-               ),
+               EditInteractionResponse::new()
+                  .add_embed(result_embed(&command.data.name, result, locale))
+                  .components(components),
             )
             .await;
          if let Err(msg) = edit_response {
             error!("Could not respond to command: {:?}", msg);
          }
+      } else if let Interaction::Component(component) = interaction {
+         let name = match component.data.custom_id.strip_prefix(actions::PLAY_BUTTON_PREFIX) {
+            Some(name) => name,
+            None => return,
+         };
+         if let Err(msg) = component.create_response(&ctx, deferred_response()).await {
+            error!("Could not respond to button: {:?}", msg);
+            return;
+         }
+
+         // the button stands in for /play, so it is only as restricted as that command
+         let locale = Locale::from_discord(&component.locale);
+         let result = match permissions::check_member(&ctx, "play", component.guild_id, component.member.as_ref()).await
+         {
+            Err(denied) => denied.text(locale),
+            Ok(_) => user_message(actions::play_button(&ctx, &component, name).await, locale),
+         };
+
+         let edit_response = component
+            .edit_response(
+               &ctx,
+               EditInteractionResponse::new().add_embed(result_embed("play", result, locale)),
+            )
+            .await;
+         if let Err(msg) = edit_response {
+            error!("Could not respond to button: {:?}", msg);
+         }
       }
    }
 }
+
+/// A placeholder that shows the bot as "thinking" to only the user who asked, until the result is ready.
+fn deferred_response() -> CreateInteractionResponse {
+   CreateInteractionResponse::Defer(CreateInteractionResponseMessage::new().flags(InteractionResponseFlags::EPHEMERAL))
+}
+
+/// Describes how a call went to whoever asked for it in their language, logging the failures that weren't their
+/// doing.
+fn user_message(result: CallResult, locale: Locale) -> String {
+   let err = match result {
+      Ok(message) => return message.text(locale),
+      Err(err) => err,
+   };
+   if !err.is_expected() {
+      error!("Unexpected error occured during call: {}", err);
+   }
+   let message = match err {
+      CallError::Invalid(reason) => reason,
+      CallError::ClipNotFound(name) => Message::new("clip_not_found").with("clip", name),
+      CallError::NotInVoiceChannel => Message::new("not_in_voice_channel"),
+      CallError::WrongChannel => Message::new("wrong_channel"),
+      CallError::Join(_) => Message::new("join_failed"),
+      CallError::ShuttingDown => Message::new("shutting_down"),
+      CallError::Failed { action, .. } => action,
+   };
+   message.text(locale)
+}
+
+fn result_embed(command: &str, result: String, locale: Locale) -> CreateEmbed {
+   CreateEmbed::new()
+      .colour(Color::FABLED_PINK)
+      .title(Message::new("result_title").with("command", command).text(locale))
+      .description(result)
+}
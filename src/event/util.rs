@@ -1,4 +1,7 @@
-use crate::audio::{audio_source, connection_data::ConnectionData, playback};
+use crate::{
+   audio::{connection_data::ConnectionData, playback},
+   guild_config::{self, GuildConfig},
+};
 use log::{error, warn};
 use serenity::{
    client::Context,
@@ -9,18 +12,24 @@ use serenity::{
 };
 use std::collections::hash_map::{HashMap, Values};
 
-fn is_afk_channel(ctx: &Context, guild_id: GuildId, channel_id: ChannelId) -> bool {
-   guild_id
-      .to_guild_cached(&ctx.cache)
-      .and_then(|guild| guild.to_owned().afk_metadata)
-      .map_or(false, |metadata| metadata.afk_channel_id == channel_id)
+/// Whether the channel is the AFK channel or one the guild has configured the bot to ignore.
+fn is_ignored_channel(ctx: &Context, config: &GuildConfig, guild_id: GuildId, channel_id: ChannelId) -> bool {
+   config.ignored_channels.contains(&channel_id)
+      || guild_id
+         .to_guild_cached(&ctx.cache)
+         .and_then(|guild| guild.to_owned().afk_metadata)
+         .is_some_and(|metadata| metadata.afk_channel_id == channel_id)
 }
 
-fn all_afk_states(ctx: &Context, guild_id: GuildId, states: Values<'_, UserId, VoiceState>) -> bool {
+fn all_ignored_states(
+   ctx: &Context, config: &GuildConfig, guild_id: GuildId, states: Values<'_, UserId, VoiceState>,
+) -> bool {
    let current_user_id = ctx.cache.current_user().id;
-   states
-      .filter(|state| state.user_id != current_user_id)
-      .all(|state| state.channel_id.map_or(true, |id| is_afk_channel(ctx, guild_id, id)))
+   states.filter(|state| state.user_id != current_user_id).all(|state| {
+      state
+         .channel_id
+         .is_none_or(|id| is_ignored_channel(ctx, config, guild_id, id))
+   })
 }
 
 fn only_user_in_channel(ctx: &Context, states: &HashMap<UserId, VoiceState>) -> bool {
@@ -34,22 +43,40 @@ fn only_user_in_channel(ctx: &Context, states: &HashMap<UserId, VoiceState>) ->
       .count()
 }
 
-pub fn moved_to_non_afk(ctx: &Context, guild_id: GuildId, channel_id: ChannelId, old_id: Option<ChannelId>) -> bool {
+pub fn moved_to_non_ignored(
+   ctx: &Context, config: &GuildConfig, guild_id: GuildId, channel_id: ChannelId, old_id: Option<ChannelId>,
+) -> bool {
    let moved_or_joined = old_id
       .map(|old_channel_id| old_channel_id != channel_id)
       .unwrap_or(true);
 
-   moved_or_joined && !is_afk_channel(ctx, guild_id, channel_id)
+   moved_or_joined && !is_ignored_channel(ctx, config, guild_id, channel_id)
+}
+
+/// Whether the bot is connected to the channel and has company there.
+pub fn bot_in_occupied_channel(ctx: &Context, guild_id: GuildId, channel_id: ChannelId) -> bool {
+   let current_user_id = ctx.cache.current_user().id;
+   guild_id.to_guild_cached(&ctx.cache).is_some_and(|guild| {
+      let states = &guild.voice_states;
+      states.get(&current_user_id).and_then(|state| state.channel_id) == Some(channel_id)
+         && states
+            .values()
+            .any(|state| state.user_id != current_user_id && state.channel_id == Some(channel_id))
+   })
 }
 
 pub async fn move_if_last_user(ctx: Context, guild_id: Option<GuildId>) {
    let current_user_id = ctx.cache.current_user().id;
+   let config = match guild_id {
+      Some(id) => guild_config::get(&ctx).await.get(id).await,
+      None => GuildConfig::default(),
+   };
    match guild_id
       .and_then(|id| id.to_guild_cached(&ctx.cache))
       .map(|guild| guild.to_owned().voice_states)
    {
       // if the bot is the only one left in voice, disconnect from voice
-      Some(states) if states.len() == 1 || all_afk_states(&ctx, guild_id.unwrap(), states.values()) => {
+      Some(states) if states.len() == 1 || all_ignored_states(&ctx, &config, guild_id.unwrap(), states.values()) => {
          let manager = playback::get_manager(&ctx).await;
          let _ = manager.leave(guild_id.unwrap()).await.map_err(|err| error!("{}", err));
       }
@@ -61,7 +88,7 @@ pub async fn move_if_last_user(ctx: Context, guild_id: Option<GuildId>) {
             .find_map(|state| {
                state
                   .channel_id
-                  .filter(|channel_id| !is_afk_channel(&ctx, guild_id.unwrap(), *channel_id))
+                  .filter(|channel_id| !is_ignored_channel(&ctx, &config, guild_id.unwrap(), *channel_id))
             });
 
          if let Some(channel_id) = first_active_channel {
@@ -69,11 +96,7 @@ pub async fn move_if_last_user(ctx: Context, guild_id: Option<GuildId>) {
                guild: guild_id.unwrap(),
                channel: channel_id,
             };
-            if let Ok(source) = audio_source::file("myman", &guild_id.unwrap()).await {
-               if let Err(err) = playback::join_connection_and_play(&ctx, connection, source, 1.0).await {
-                  error!("Failed to join another active channel: {}", err);
-               }
-            } else if let Err(err) = playback::join_connection(&ctx, connection).await {
+            if let Err(err) = playback::join_connection_and_announce(&ctx, connection, None).await {
                error!("Failed to join another active channel: {}", err);
             }
          } else {
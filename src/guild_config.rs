@@ -0,0 +1,331 @@
+use log::{error, warn};
+use serde::{Deserialize, Serialize};
+use serenity::{
+   builder::CreateMessage,
+   client::Context,
+   model::{
+      guild::Guild,
+      id::{ChannelId, GuildId},
+   },
+   prelude::{RwLock, TypeMapKey},
+};
+use sha2::{Digest, Sha256};
+use std::{
+   collections::HashMap,
+   fs,
+   io::{Error, ErrorKind},
+   path::PathBuf,
+   sync::Arc,
+};
+
+use crate::{audio::audio_source, commands, rate_limit::RateLimitConfig, role};
+
+/// The loudest volume a guild or clip can be set to, as a multiplier.
+pub const MAX_VOLUME: f32 = 2.0;
+
+// A little quieter than most streaming services, since clips often play over people talking
+const DEFAULT_LOUDNESS_TARGET: f32 = -18.0;
+
+// Holds a hash of the problems last reported for a guild, so restarts and reconnects don't report them again
+const REPORTED_PROBLEMS_FILE: &str = ".config_problems_reported";
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GuildConfig {
+   /// Play a user's entrance sound when they join a voice channel
+   pub entrances: bool,
+   /// Play requested clips one after another instead of mixing them together
+   pub queue: bool,
+   /// Let entrance and exit sounds play immediately even when the queue is enabled
+   pub overlap_entrances: bool,
+   /// Play a user's exit sound in the channel they left when the bot stays behind with others
+   pub exit_sounds: bool,
+   /// Default playback volume, where 1.0 plays clips unchanged
+   pub volume: f32,
+   /// Adjust each clip's gain so that all of them play at about `loudness_target`, which guilds opt into since it
+   /// changes how loud existing clips play
+   pub normalize_loudness: bool,
+   /// Integrated loudness, in LUFS, that clips are brought to when normalizing
+   pub loudness_target: f32,
+   /// Voice channels treated like the AFK channel: no entrances, and the bot won't follow users into them
+   pub ignored_channels: Vec<ChannelId>,
+   /// Commands, without the leading slash, that only members with the Sound Clip Admin role may use
+   pub admin_commands: Vec<String>,
+   /// How quickly members and the guild as a whole may play clips
+   pub rate_limit: RateLimitConfig,
+}
+
+impl Default for GuildConfig {
+   fn default() -> Self {
+      Self {
+         entrances: true,
+         queue: false,
+         overlap_entrances: false,
+         exit_sounds: false,
+         volume: 1.0,
+         normalize_loudness: false,
+         loudness_target: DEFAULT_LOUDNESS_TARGET,
+         ignored_channels: Vec::new(),
+         admin_commands: Vec::new(),
+         rate_limit: RateLimitConfig::default(),
+      }
+   }
+}
+
+impl GuildConfig {
+   /// Lists every problem with the settings that parsing alone can't catch.
+   fn problems(&self, guild: Option<&Guild>) -> Vec<String> {
+      let mut problems = Vec::new();
+      if !(0.0..=MAX_VOLUME).contains(&self.volume) {
+         problems.push(format!("`volume` must be between 0.0 and {MAX_VOLUME:.1}"));
+      }
+      if !(-70.0..=0.0).contains(&self.loudness_target) {
+         problems.push("`loudness_target` must be between -70.0 and 0.0 LUFS".to_string());
+      }
+      let known_commands = commands::names();
+      for command in &self.admin_commands {
+         if !known_commands.contains(&command.as_str()) {
+            problems.push(format!("`admin_commands` contains unknown command `{command}`"));
+         }
+      }
+      problems.extend(self.rate_limit.problems());
+      if let Some(guild) = guild {
+         for channel_id in &self.ignored_channels {
+            if !guild.channels.contains_key(channel_id) {
+               problems.push(format!(
+                  "`ignored_channels` contains {channel_id}, which is not a channel here"
+               ));
+            }
+         }
+      }
+      problems
+   }
+}
+
+/// Per-guild settings read from `config.toml` in each guild's clip directory.
+///
+/// Settings are loaded when the guild becomes available, or on first use if that happens sooner.
+#[derive(Default)]
+pub struct GuildConfigs {
+   configs: RwLock<HashMap<GuildId, GuildConfig>>,
+}
+
+impl TypeMapKey for GuildConfigs {
+   type Value = Arc<GuildConfigs>;
+}
+
+impl GuildConfigs {
+   pub async fn get(&self, guild_id: GuildId) -> GuildConfig {
+      if let Some(config) = self.configs.read().await.get(&guild_id) {
+         return config.clone();
+      }
+      let config = read(&guild_id, None).unwrap_or_else(|problems| {
+         warn!("Invalid config.toml for {:?}, using defaults: {:?}", guild_id, problems);
+         GuildConfig::default()
+      });
+      self.configs.write().await.insert(guild_id, config.clone());
+      config
+   }
+
+   /// Re-reads the guild's `config.toml`, falling back to the defaults and returning the problems if it is invalid.
+   pub async fn reload(&self, guild: &Guild) -> Result<GuildConfig, Vec<String>> {
+      let result = read(&guild.id, Some(guild));
+      if result.is_ok() {
+         // once fixed, the same problems are worth reporting again should they come back
+         let _ = fs::remove_file(audio_source::guild_dir(&guild.id).join(REPORTED_PROBLEMS_FILE));
+      }
+      let config = result.clone().unwrap_or_default();
+      self.configs.write().await.insert(guild.id, config);
+      result
+   }
+
+   /// Applies a change to a guild's settings and writes them back to its `config.toml`.
+   ///
+   /// An invalid file is left untouched so that the changes made to it are not lost.
+   pub async fn update<F: FnOnce(&mut GuildConfig)>(&self, guild_id: GuildId, change: F) -> Result<GuildConfig, Error> {
+      let mut config = read(&guild_id, None).map_err(|problems| {
+         Error::new(
+            ErrorKind::InvalidData,
+            format!("config.toml must be fixed first: {}", problems.join(", ")),
+         )
+      })?;
+      change(&mut config);
+
+      let content = toml::to_string(&config).map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+      fs::write(path(&guild_id), content)?;
+      self.configs.write().await.insert(guild_id, config.clone());
+      Ok(config)
+   }
+}
+
+pub async fn get(ctx: &Context) -> Arc<GuildConfigs> {
+   ctx.data
+      .read()
+      .await
+      .get::<GuildConfigs>()
+      .cloned()
+      .expect("Guild configs should have been placed during initialization")
+}
+
+/// Tells the guild's admins what is wrong with its config, in the system channel or else by messaging the owner.
+///
+/// Problems that were already reported are only logged, so they are reported once until they change.
+pub async fn report_problems(ctx: &Context, guild: &Guild, problems: &[String]) {
+   let reported = audio_source::guild_dir(&guild.id).join(REPORTED_PROBLEMS_FILE);
+   let digest = problems_digest(problems);
+   if fs::read_to_string(&reported).is_ok_and(|last| last.trim() == digest) {
+      warn!("config.toml for {:?} is still invalid: {:?}", guild.id, problems);
+      return;
+   }
+   if send_problems(ctx, guild, problems).await {
+      if let Err(err) = fs::write(&reported, digest) {
+         warn!(
+            "Could not remember the config problems reported for {:?}: {:?}",
+            guild.id, err
+         );
+      }
+   }
+}
+
+async fn send_problems(ctx: &Context, guild: &Guild, problems: &[String]) -> bool {
+   let mention = role::admin_role_id(&guild.id).map_or_else(String::new, |role_id| format!("<@&{role_id}> "));
+   let content = format!(
+      "{mention}My config.toml for **{}** is invalid, so the default settings are in use until it is fixed:\n{}",
+      guild.name,
+      problems
+         .iter()
+         .map(|problem| format!("- {problem}"))
+         .collect::<Vec<_>>()
+         .join("\n")
+   );
+
+   if let Some(channel_id) = guild.system_channel_id {
+      match channel_id.say(ctx, &content).await {
+         Ok(_) => return true,
+         Err(err) => warn!("Could not report config problems in {:?}: {:?}", guild.id, err),
+      }
+   }
+   match guild
+      .owner_id
+      .direct_message(ctx, CreateMessage::new().content(content))
+      .await
+   {
+      Ok(_) => true,
+      Err(err) => {
+         error!(
+            "Could not report config problems for {:?} to its owner: {:?}",
+            guild.id, err
+         );
+         false
+      }
+   }
+}
+
+fn problems_digest(problems: &[String]) -> String {
+   Sha256::digest(problems.join("\0").as_bytes())
+      .iter()
+      .map(|byte| format!("{byte:02x}"))
+      .collect()
+}
+
+fn path(guild_id: &GuildId) -> PathBuf {
+   audio_source::guild_dir(guild_id).join("config.toml")
+}
+
+fn read(guild_id: &GuildId, guild: Option<&Guild>) -> Result<GuildConfig, Vec<String>> {
+   match fs::read_to_string(path(guild_id)) {
+      Ok(content) => parse(&content, guild),
+      Err(err) if err.kind() == ErrorKind::NotFound => Ok(GuildConfig::default()),
+      Err(err) => Err(vec![format!("config.toml could not be read: {err}")]),
+   }
+}
+
+fn parse(content: &str, guild: Option<&Guild>) -> Result<GuildConfig, Vec<String>> {
+   let config: GuildConfig = toml::from_str(content).map_err(|err| vec![err.message().to_string()])?;
+   let problems = config.problems(guild);
+   if problems.is_empty() {
+      Ok(config)
+   } else {
+      Err(problems)
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn test_problems_digest_changes_with_problems() {
+      let problems = vec!["volume must be between 0 and 2".to_string()];
+
+      assert_eq!(problems_digest(&problems), problems_digest(&problems.clone()));
+      assert_ne!(
+         problems_digest(&problems),
+         problems_digest(&["queue must be a boolean".to_string()])
+      );
+      assert_ne!(
+         problems_digest(&["a\nb".to_string()]),
+         problems_digest(&["a".to_string(), "b".to_string()])
+      );
+   }
+
+   #[test]
+   fn test_round_trip() {
+      let config = GuildConfig {
+         queue: true,
+         volume: 0.5,
+         ignored_channels: vec![ChannelId::new(1234)],
+         ..Default::default()
+      };
+
+      assert_eq!(parse(&toml::to_string(&config).unwrap(), None), Ok(config));
+   }
+
+   #[test]
+   fn test_missing_keys_default() {
+      assert_eq!(
+         parse("queue = true", None),
+         Ok(GuildConfig {
+            queue: true,
+            ..Default::default()
+         })
+      );
+   }
+
+   #[test]
+   fn test_channel_ids_parsed() {
+      let config = parse("ignored_channels = [1234, \"5678\"]", None).unwrap();
+
+      assert_eq!(
+         config.ignored_channels,
+         vec![ChannelId::new(1234), ChannelId::new(5678)]
+      );
+   }
+
+   #[test]
+   fn test_unknown_keys_rejected() {
+      assert!(parse("queueu = true", None).is_err());
+   }
+
+   #[test]
+   fn test_invalid_values_reported() {
+      let problems = parse("volume = 3.0\nadmin_commands = [\"stop\", \"explode\"]", None).unwrap_err();
+
+      assert_eq!(problems.len(), 2);
+      assert!(problems[0].contains("volume"));
+      assert!(problems[1].contains("explode"));
+   }
+
+   #[test]
+   fn test_rate_limit_table_parsed() {
+      let config = parse("[rate_limit]\nuser_burst = 2\nguild_per_minute = 0", None).unwrap();
+
+      assert_eq!(config.rate_limit.user_burst, 2);
+      assert_eq!(config.rate_limit.guild_per_minute, 0);
+      assert_eq!(
+         config.rate_limit.user_per_minute,
+         RateLimitConfig::default().user_per_minute
+      );
+      assert!(parse("[rate_limit]\nuser_burst = 0", None).is_err());
+   }
+}
@@ -0,0 +1,323 @@
+use log::error;
+use serde::{Deserialize, Serialize};
+use serenity::model::id::{GuildId, UserId};
+use std::{
+   collections::HashMap,
+   fs::{self, OpenOptions},
+   hash::Hash,
+   io::{Error, ErrorKind, Write},
+   path::{Path, PathBuf},
+   sync::Mutex,
+   time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::audio::audio_source;
+
+const FILE_NAME: &str = "history.toml";
+// Once the file grows past this many bytes, all but the latest plays are folded into running counts, which keeps it
+// quick to read for /stats
+const COMPACT_SIZE: u64 = 256 * 1024;
+// How many of the latest plays are kept whole when the file is compacted
+const KEEP_RECENT: usize = 100;
+
+// Plays are appended from every path that starts one, so appends must not interleave
+static HISTORY_LOCK: Mutex<()> = Mutex::new(());
+
+/// What started a play.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlaySource {
+   /// A slash command or one of the buttons they show
+   Command,
+   /// The web server's play route
+   Http,
+   Entrance,
+   Exit,
+   /// The bot announcing itself as it joins a channel
+   Summon,
+}
+
+/// Who or what asked for a play, recorded in the guild's history once it starts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Origin {
+   pub source: PlaySource,
+   /// The member who asked, if a member did
+   pub user: Option<UserId>,
+}
+
+impl Origin {
+   pub fn new(source: PlaySource, user: UserId) -> Self {
+      Self {
+         source,
+         user: Some(user),
+      }
+   }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Play {
+   pub clip: String,
+   pub user: Option<UserId>,
+   pub source: PlaySource,
+   /// Seconds since the Unix epoch
+   pub timestamp: u64,
+}
+
+/// A guild's plays as stored in its `history.toml`, where plays too old to be kept whole are only counted.
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct History {
+   #[serde(default, skip_serializing_if = "Totals::is_empty")]
+   totals: Totals,
+   #[serde(default)]
+   plays: Vec<Play>,
+}
+
+impl History {
+   pub fn is_empty(&self) -> bool {
+      self.totals.is_empty() && self.plays.is_empty()
+   }
+}
+
+/// Counts of the plays folded out of a history, each list in order of first play so ties rank as they did.
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+struct Totals {
+   plays: usize,
+   clips: Vec<(String, usize)>,
+   users: Vec<(UserId, usize)>,
+}
+
+impl Totals {
+   fn is_empty(&self) -> bool {
+      self.plays == 0
+   }
+
+   fn add(&mut self, play: &Play) {
+      self.plays += 1;
+      increment(&mut self.clips, &play.clip);
+      if let Some(user) = &play.user {
+         increment(&mut self.users, user);
+      }
+   }
+}
+
+/// The leaderboards `/stats` shows for a guild.
+#[derive(Debug, PartialEq)]
+pub struct Stats {
+   pub total: usize,
+   pub top_clips: Vec<(String, usize)>,
+   pub top_users: Vec<(UserId, usize)>,
+   /// Newest first
+   pub recent: Vec<Play>,
+}
+
+/// Appends a play to the guild's `history.toml`, logging rather than failing the play if that isn't possible.
+pub fn record(guild_id: &GuildId, clip: &str, origin: Origin) {
+   let play = Play {
+      clip: clip.to_owned(),
+      user: origin.user,
+      source: origin.source,
+      timestamp: SystemTime::now()
+         .duration_since(UNIX_EPOCH)
+         .map_or(0, |duration| duration.as_secs()),
+   };
+   if let Err(err) = append(guild_id, play) {
+      error!("Could not record a play of {} in {:?}: {:?}", clip, guild_id, err);
+   }
+}
+
+/// Reads the guild's history, which is kept small enough to read whole.
+pub fn load(guild_id: &GuildId) -> Result<History, Error> {
+   read(&path(guild_id))
+}
+
+pub fn stats(history: &History, limit: usize) -> Stats {
+   let totals = &history.totals;
+   let plays = &history.plays;
+   Stats {
+      total: totals.plays + plays.len(),
+      top_clips: top(
+         totals
+            .clips
+            .iter()
+            .cloned()
+            .chain(plays.iter().map(|play| (play.clip.clone(), 1))),
+         limit,
+      ),
+      top_users: top(
+         totals
+            .users
+            .iter()
+            .copied()
+            .chain(plays.iter().filter_map(|play| play.user).map(|user| (user, 1))),
+         limit,
+      ),
+      recent: plays.iter().rev().take(limit).cloned().collect(),
+   }
+}
+
+fn append(guild_id: &GuildId, play: Play) -> Result<(), Error> {
+   // each play is its own `[[plays]]` table, so appending one keeps the file valid
+   let content = toml::to_string(&History {
+      plays: vec![play],
+      ..Default::default()
+   })
+   .map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+   let _lock = HISTORY_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+   let path = path(guild_id);
+   let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+   writeln!(file, "{content}")?;
+   if file.metadata()?.len() > COMPACT_SIZE {
+      compact(&path)?;
+   }
+   Ok(())
+}
+
+/// Folds all but the latest plays into the history's running counts.
+fn compact(path: &Path) -> Result<(), Error> {
+   let mut history = read(path)?;
+   let folded = history.plays.len().saturating_sub(KEEP_RECENT);
+   for play in history.plays.drain(..folded) {
+      history.totals.add(&play);
+   }
+   let content = toml::to_string(&history).map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+   // written beside the history and moved over it, so a crash midway can't lose it
+   let compacted = path.with_extension("toml.tmp");
+   fs::write(&compacted, content)?;
+   fs::rename(compacted, path)
+}
+
+fn read(path: &Path) -> Result<History, Error> {
+   match fs::read_to_string(path) {
+      Ok(content) => toml::from_str(&content).map_err(|err| Error::new(ErrorKind::InvalidData, err)),
+      Err(err) if err.kind() == ErrorKind::NotFound => Ok(History::default()),
+      Err(err) => Err(err),
+   }
+}
+
+fn increment<K: Clone + PartialEq>(counts: &mut Vec<(K, usize)>, key: &K) {
+   match counts.iter_mut().find(|(counted, _)| counted == key) {
+      Some((_, count)) => *count += 1,
+      None => counts.push((key.clone(), 1)),
+   }
+}
+
+/// Adds up the counts for each key, most frequent first and in order of first appearance among ties.
+fn top<K: Clone + Eq + Hash>(counted: impl Iterator<Item = (K, usize)>, limit: usize) -> Vec<(K, usize)> {
+   let mut counts: HashMap<K, (usize, usize)> = HashMap::new();
+   for (position, (key, count)) in counted.enumerate() {
+      counts.entry(key).or_insert((0, position)).0 += count;
+   }
+   let mut counts = counts.into_iter().collect::<Vec<_>>();
+   counts.sort_by(|(_, (a_count, a_first)), (_, (b_count, b_first))| b_count.cmp(a_count).then(a_first.cmp(b_first)));
+   counts
+      .into_iter()
+      .take(limit)
+      .map(|(key, (count, _))| (key, count))
+      .collect()
+}
+
+fn path(guild_id: &GuildId) -> PathBuf {
+   audio_source::guild_dir(guild_id).join(FILE_NAME)
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+   use crate::audio::audio_source::tests::{lock_env, setup_temp_directories};
+
+   #[test]
+   fn test_plays_persisted() -> Result<(), Error> {
+      let _lock = lock_env();
+      let dir = setup_temp_directories()?;
+      let guild_id = GuildId::new(1);
+
+      assert!(load(&guild_id)?.is_empty());
+      record(&guild_id, "clip", Origin::new(PlaySource::Command, UserId::new(7)));
+      record(
+         &guild_id,
+         "another_clip",
+         Origin {
+            source: PlaySource::Summon,
+            user: None,
+         },
+      );
+
+      let plays = load(&guild_id)?.plays;
+      assert_eq!(plays.len(), 2);
+      assert_eq!(
+         (plays[0].clip.as_str(), plays[0].user, plays[0].source),
+         ("clip", Some(UserId::new(7)), PlaySource::Command)
+      );
+      assert_eq!((plays[1].user, plays[1].source), (None, PlaySource::Summon));
+      assert!(load(&GuildId::new(2))?.is_empty());
+
+      dir.close()?;
+      Ok(())
+   }
+
+   #[test]
+   fn test_stats_ranked() {
+      let play = |clip: &str, user: u64, timestamp| Play {
+         clip: clip.to_string(),
+         user: Some(UserId::new(user)),
+         source: PlaySource::Command,
+         timestamp,
+      };
+      let history = History {
+         plays: vec![
+            play("a", 1, 1),
+            play("b", 2, 2),
+            play("b", 2, 3),
+            play("c", 1, 4),
+            play("b", 3, 5),
+         ],
+         ..Default::default()
+      };
+
+      let stats = stats(&history, 2);
+      assert_eq!(stats.total, 5);
+      assert_eq!(stats.top_clips, [("b".to_string(), 3), ("a".to_string(), 1)]);
+      assert_eq!(stats.top_users, [(UserId::new(1), 2), (UserId::new(2), 2)]);
+      assert_eq!(
+         stats.recent.iter().map(|play| play.timestamp).collect::<Vec<_>>(),
+         [5, 4]
+      );
+   }
+
+   #[test]
+   fn test_old_plays_folded_into_counts() -> Result<(), Error> {
+      let _lock = lock_env();
+      let dir = setup_temp_directories()?;
+      let guild_id = GuildId::new(1);
+      let plays = (0..KEEP_RECENT + 50)
+         .map(|i| Play {
+            clip: if i < 30 { "old" } else { "new" }.to_string(),
+            user: (i % 2 == 0).then_some(UserId::new(7)),
+            source: PlaySource::Command,
+            timestamp: i as u64,
+         })
+         .collect::<Vec<_>>();
+      let history = History {
+         plays,
+         ..Default::default()
+      };
+      let before = stats(&history, 5);
+      fs::write(path(&guild_id), toml::to_string(&history).unwrap())?;
+
+      compact(&path(&guild_id))?;
+      let compacted = load(&guild_id)?;
+      assert_eq!(compacted.plays.len(), KEEP_RECENT);
+      assert_eq!(compacted.totals.plays, 50);
+      assert_eq!(stats(&compacted, 5), before);
+
+      // plays appended afterwards still count
+      record(&guild_id, "old", Origin::new(PlaySource::Http, UserId::new(7)));
+      let after = stats(&load(&guild_id)?, 5);
+      assert_eq!(after.total, before.total + 1);
+      assert_eq!(after.top_users, [(UserId::new(7), before.top_users[0].1 + 1)]);
+
+      dir.close()?;
+      Ok(())
+   }
+}
@@ -1,29 +1,287 @@
-use rocket::{catch, http::Status, post, Request, State};
+use log::error;
+use rocket::{
+   catch, get,
+   http::{Header, Status},
+   post,
+   response::content::RawHtml,
+   serde::json::Json,
+   Request, Responder, State,
+};
 use serenity::{
    http::CacheHttp,
    model::id::{GuildId, UserId},
 };
+use songbird::tracks::PlayMode;
+use std::time::{Duration, UNIX_EPOCH};
 
 use crate::{
-   audio::{connection_data::ConnectionData, playback::play_file_with_manager},
-   WebContext,
+   api_tokens::ApiUser,
+   audio::{
+      audio_source::{self, ClipInfo},
+      clip_index,
+      connection_data::ConnectionData,
+      playback::{play_file_with_manager, stop_with_manager},
+      tracks,
+   },
+   call_result::CallError,
+   guild_config::GuildConfig,
+   guilds::get_guild,
+   history::{Origin, PlaySource},
+   permissions, search, WebContext,
 };
 
 #[catch(default)]
 pub fn default_catcher(_status: Status, _request: &Request) {}
 
-#[post("/play/<guild_id>/<user_id>/<name>")]
-pub async fn play(ctx: &State<WebContext>, guild_id: u64, user_id: u64, name: &str) -> Result<(), Status> {
-   if let Ok(user) = UserId::new(user_id).to_user((&ctx.cache, ctx.http.http())).await {
-      if let Some(connect_to) = ConnectionData::try_from_guild_user(&ctx.cache, GuildId::new(guild_id), &user) {
-         return match play_file_with_manager(ctx.songbird.clone(), name, connect_to)
-            .await
-            .underlying_error
-         {
-            Some(_) => Err(Status::InternalServerError),
-            None => Ok(()),
-         };
-      };
-   }
-   Err(Status::NotFound)
+#[derive(Responder)]
+pub enum PlayError {
+   #[response(status = 429)]
+   RateLimited((), Header<'static>),
+   Failed(Status),
+}
+
+#[post("/play/<guild_id>/<name>")]
+pub async fn play(ctx: &State<WebContext>, user: ApiUser, guild_id: u64, name: &str) -> Result<(), PlayError> {
+   let user = match user.0.to_user((&ctx.cache, ctx.http.http())).await {
+      Ok(user) => user,
+      Err(_) => return Err(PlayError::Failed(Status::ServiceUnavailable)),
+   };
+   let connect_to = ConnectionData::try_from_guild_user(&ctx.cache, GuildId::new(guild_id), &user)
+      .ok_or(PlayError::Failed(status(&CallError::NotInVoiceChannel)))?;
+
+   // the web server answers to the same `admin_commands` as the commands that do the same
+   let config = ctx.configs.get(connect_to.guild).await;
+   if !allowed(ctx, &config, "play", connect_to.guild, user.id) {
+      return Err(PlayError::Failed(Status::Forbidden));
+   }
+   if let Err(wait) = ctx.rate_limiter.check(connect_to.guild, user.id, &config.rate_limit) {
+      return Err(PlayError::RateLimited((), retry_after(wait)));
+   }
+   play_file_with_manager(
+      ctx.songbird.clone(),
+      ctx.configs.clone(),
+      ctx.tracks.clone(),
+      name,
+      connect_to,
+      Origin::new(PlaySource::Http, user.id),
+   )
+   .await
+   .map(|_| ())
+   .map_err(|err| {
+      if !err.is_expected() {
+         error!("Failed to play {} over HTTP: {}", name, err);
+      }
+      PlayError::Failed(status(&err))
+   })
+}
+
+#[post("/stop/<guild_id>")]
+pub async fn stop(ctx: &State<WebContext>, user: ApiUser, guild_id: u64) -> Result<(), Status> {
+   let user = user
+      .0
+      .to_user((&ctx.cache, ctx.http.http()))
+      .await
+      .map_err(|_| Status::ServiceUnavailable)?;
+   let connect_to = ConnectionData::try_from_guild_user(&ctx.cache, GuildId::new(guild_id), &user)
+      .ok_or(status(&CallError::NotInVoiceChannel))?;
+
+   let config = ctx.configs.get(connect_to.guild).await;
+   if !allowed(ctx, &config, "stop", connect_to.guild, user.id) {
+      return Err(Status::Forbidden);
+   }
+   stop_with_manager(ctx.songbird.clone(), connect_to)
+      .await
+      .map(|_| ())
+      .map_err(|err| status(&err))
+}
+
+/// The titles of the tracks playing in the guild right now, leaving out any still waiting in the queue. Only the
+/// guild's members may see them.
+#[get("/playing/<guild_id>")]
+pub async fn playing(ctx: &State<WebContext>, user: ApiUser, guild_id: u64) -> Result<Json<Vec<String>>, Status> {
+   let guild = get_guild(&ctx.cache, guild_id).ok_or(Status::NotFound)?;
+   if guild.member((&ctx.cache, ctx.http.http()), user.0).await.is_err() {
+      return Err(Status::Forbidden);
+   }
+
+   let mut titles = Vec::new();
+   for handle in ctx.tracks.current(GuildId::new(guild_id)).await {
+      if handle
+         .get_info()
+         .await
+         .is_ok_and(|state| state.playing == PlayMode::Play)
+      {
+         titles.push(tracks::title(&handle).await);
+      }
+   }
+   Ok(Json(titles))
+}
+
+#[get("/clips/<guild_id>?<q>", format = "json")]
+pub async fn clips_json(
+   ctx: &State<WebContext>, guild_id: u64, q: Option<&str>,
+) -> Result<Json<Vec<ClipInfo>>, Status> {
+   guild_clips(ctx, guild_id, q).await.map(|(_, clips)| Json(clips))
+}
+
+#[get("/clips/<guild_id>?<q>", rank = 2)]
+pub async fn clips_html(ctx: &State<WebContext>, guild_id: u64, q: Option<&str>) -> Result<RawHtml<String>, Status> {
+   let (guild_name, clips) = guild_clips(ctx, guild_id, q).await?;
+   let rows = clips
+      .iter()
+      .map(|clip| {
+         let details = &clip.details;
+         let cells = [
+            escape_html(&clip.name),
+            escape_html(&clip.tags.join(", ")),
+            details.duration_ms.map(clip_index::format_duration).unwrap_or_default(),
+            clip.format.clone(),
+            details.sample_rate.map(|rate| format!("{rate} Hz")).unwrap_or_default(),
+            details
+               .channels
+               .map(|channels| channels.to_string())
+               .unwrap_or_default(),
+            details
+               .loudness_lufs
+               .map(|lufs| format!("{lufs:.1} LUFS"))
+               .unwrap_or_default(),
+            format_size(clip.size),
+            clip
+               .uploader
+               .map(|uploader| escape_html(&uploader_name(ctx, uploader)))
+               .unwrap_or_default(),
+            format_timestamp(clip.added),
+            format_timestamp(clip.modified),
+         ];
+         format!("<tr>{}</tr>", cells.map(|cell| format!("<td>{cell}</td>")).concat())
+      })
+      .collect::<String>();
+   let title = escape_html(&guild_name);
+   let query = escape_html(q.unwrap_or_default());
+
+   Ok(RawHtml(format!(
+      "<!DOCTYPE html>
+<html>
+<head><meta charset=\"utf-8\"><title>{title} clips</title></head>
+<body>
+<h1>{title}</h1>
+<form method=\"get\"><input type=\"search\" name=\"q\" value=\"{query}\" placeholder=\"Search names and tags\"> <button>Search</button></form>
+<table>
+<thead><tr><th>Name</th><th>Tags</th><th>Duration</th><th>Format</th><th>Sample rate</th><th>Channels</th><th>Loudness</th><th>Size</th><th>Uploader</th><th>Added</th><th>Modified</th></tr></thead>
+<tbody>{rows}</tbody>
+</table>
+</body>
+</html>"
+   )))
+}
+
+/// Lists a guild's clips, narrowed down and ordered by relevance when there is a search query.
+pub async fn guild_clips(
+   ctx: &WebContext, guild_id: u64, query: Option<&str>,
+) -> Result<(String, Vec<ClipInfo>), Status> {
+   let guild = get_guild(&ctx.cache, guild_id).ok_or(Status::NotFound)?;
+   match audio_source::clips(&guild.id).await {
+      Ok(clips) => Ok((guild.name, search::rank(clips, query.unwrap_or_default()))),
+      Err(_) => Err(Status::InternalServerError),
+   }
+}
+
+fn uploader_name(ctx: &WebContext, uploader: UserId) -> String {
+   ctx.cache
+      .user(uploader)
+      .map_or_else(|| uploader.to_string(), |user| user.name.clone())
+}
+
+fn format_timestamp(seconds: u64) -> String {
+   humantime::format_rfc3339_seconds(UNIX_EPOCH + Duration::from_secs(seconds)).to_string()
+}
+
+/// Whether the member may do what the named command does, under the guild's `admin_commands`.
+fn allowed(ctx: &WebContext, config: &GuildConfig, command: &str, guild_id: GuildId, user_id: UserId) -> bool {
+   let member = get_guild(&ctx.cache, guild_id).and_then(|guild| guild.members.get(&user_id).cloned());
+   permissions::check_with_config(config, command, Some(guild_id), member.as_ref()).is_ok()
+}
+
+/// The status that best describes why a play couldn't start.
+fn status(err: &CallError) -> Status {
+   match err {
+      CallError::Invalid(_) => Status::BadRequest,
+      CallError::ClipNotFound(_) => Status::NotFound,
+      // the member needs to be where the bot can play to them, which they can fix and retry
+      CallError::NotInVoiceChannel | CallError::WrongChannel => Status::Conflict,
+      CallError::Join(_) | CallError::ShuttingDown => Status::ServiceUnavailable,
+      CallError::Failed { .. } => Status::InternalServerError,
+   }
+}
+
+/// Builds a `Retry-After` header, rounding up so clients never retry too early.
+fn retry_after(wait: Duration) -> Header<'static> {
+   Header::new("Retry-After", (wait.as_secs_f64().ceil() as u64).max(1).to_string())
+}
+
+pub fn escape_html(text: &str) -> String {
+   text
+      .chars()
+      .map(|c| match c {
+         '&' => "&amp;".to_string(),
+         '<' => "&lt;".to_string(),
+         '>' => "&gt;".to_string(),
+         '"' => "&quot;".to_string(),
+         '\'' => "&#39;".to_string(),
+         _ => c.to_string(),
+      })
+      .collect()
+}
+
+fn format_size(bytes: u64) -> String {
+   match bytes {
+      0..=1023 => format!("{bytes} B"),
+      1024..=1_048_575 => format!("{:.1} KiB", bytes as f64 / 1024.0),
+      _ => format!("{:.1} MiB", bytes as f64 / 1_048_576.0),
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+   use crate::locale::Message;
+   use songbird::error::JoinError;
+
+   #[test]
+   fn test_html_escaped() {
+      assert_eq!(
+         escape_html("<b>\"Tom & Jerry's\"</b>"),
+         "&lt;b&gt;&quot;Tom &amp; Jerry&#39;s&quot;&lt;/b&gt;"
+      );
+   }
+
+   #[test]
+   fn test_size_formatted() {
+      assert_eq!(format_size(512), "512 B");
+      assert_eq!(format_size(1536), "1.5 KiB");
+      assert_eq!(format_size(3 * 1024 * 1024), "3.0 MiB");
+   }
+
+   #[test]
+   fn test_retry_after_rounds_up() {
+      assert_eq!(retry_after(Duration::from_millis(1200)).value(), "2");
+      assert_eq!(retry_after(Duration::ZERO).value(), "1");
+   }
+
+   #[test]
+   fn test_play_errors_mapped_to_statuses() {
+      assert_eq!(status(&CallError::ClipNotFound("clip".to_string())), Status::NotFound);
+      assert_eq!(status(&CallError::WrongChannel), Status::Conflict);
+      assert_eq!(
+         status(&CallError::Join(JoinError::TimedOut)),
+         Status::ServiceUnavailable
+      );
+      assert_eq!(
+         status(&CallError::failed(
+            Message::new("failed_load_clip").with("clip", "clip"),
+            "unreadable"
+         )),
+         Status::InternalServerError
+      );
+   }
 }
@@ -0,0 +1,217 @@
+// Command names are left as they are so /help, the README and `admin_commands` use the same names in every language
+pub const MESSAGES: &[(&str, &str)] = &[
+   (
+      "help",
+      "Du kannst die folgenden Befehle verwenden:
+```
+/list    - Listet die verfügbaren Sounds auf.
+/play    - Spielt den angegebenen Sound aus der Liste ab.
+/search  - Findet Sounds nach Name oder Tag, mit Knöpfen zum Abspielen.
+/random  - Spielt einen zufälligen Sound ab, auf Wunsch einen, dessen Name einen Filter enthält.
+/youtube - Spielt den angegebenen YouTube-Link ab.
+/upload  - Fügt einen Audio-Anhang zu den Sounds hinzu (nur Sound Clip Admins).
+/trim    - Schneidet einen Sound oder Anhang auf einen Bereich zu oder entfernt seine Stille (nur Sound Clip Admins).
+/stop    - Stoppt die gerade laufenden Sounds.
+/queue   - Zeigt die Sounds, die darauf warten, abgespielt zu werden.
+/volume  - Zeigt oder ändert die Lautstärke des Servers oder eines Sounds (Ändern nur für Sound Clip Admins).
+/skip    - Springt zum nächsten Sound in der Warteschlange.
+/stats   - Zeigt die meistgespielten Sounds, die aktivsten Mitglieder und die letzten Wiedergaben.
+/tags    - Zeigt oder ändert die Tags, über die ein Sound gefunden wird (Ändern nur für Sound Clip Admins).
+/summon  - Holt den Bot in deinen aktuellen Sprachkanal.
+/token   - Gibt dir einen privaten Token, um Sounds über den Webserver abzuspielen.
+```",
+   ),
+   ("result_title", "Ergebnis von /{command}"),
+   ("unrecognized_command", "Unbekannter Befehl!"),
+   ("clip_not_found", "Keine Audiodatei für {clip} gefunden"),
+   ("not_in_voice_channel", "Du bist in keinem Sprachkanal!"),
+   ("wrong_channel", "Der Bot ist gerade nicht in deinem Kanal"),
+   ("join_failed", "Der Bot konnte deinem Kanal nicht beitreten"),
+   ("shutting_down", "Der Bot fährt gerade herunter, versuche es wieder, sobald er zurück ist"),
+   ("admin_required", "Du brauchst die Rolle Sound Clip Admin, um /{command} auf diesem Server zu verwenden"),
+   ("command_outside_server", "/{command} kann nur auf einem Server verwendet werden"),
+   ("rate_limited", "Du spielst Sounds zu schnell ab, versuche es in {seconds} Sekunden wieder"),
+   ("rate_limited_one", "Du spielst Sounds zu schnell ab, versuche es in 1 Sekunde wieder"),
+   ("playing", "Spiele {clip}"),
+   ("playing_with_details", "Spiele {clip} ({details})"),
+   ("queued", "{clip} ist in der Warteschlange"),
+   ("queued_with_details", "{clip} ist in der Warteschlange ({details})"),
+   ("cannot_parse_file_name", "Der Dateiname konnte nicht gelesen werden"),
+   ("cannot_parse_url", "Die YouTube-URL konnte nicht gelesen werden"),
+   ("invalid_url", "{url} ist keine gültige URL"),
+   ("failed_load_clip", "Die Datei für {clip} konnte nicht geladen werden"),
+   ("no_clips", "Es gibt keine Sounds zum Abspielen"),
+   ("no_matching_clips", "Keine Sounds passen zu {query}"),
+   ("failed_list_clips", "Die Sounds konnten nicht aufgelistet werden"),
+   ("summoned", "Bot herbeigerufen"),
+   ("playback_stopped", "Wiedergabe gestoppt"),
+   ("skipped", "{clip} übersprungen"),
+   ("failed_skip", "Der aktuelle Sound konnte nicht übersprungen werden"),
+   ("nothing_queued", "Die Warteschlange ist leer"),
+   ("bot_joined", "Ein Bot ist einem Kanal beigetreten: {user}"),
+   ("bot_left", "Ein Bot hat einen Kanal verlassen: {user}"),
+   ("entrances_disabled", "Eintrittssounds sind deaktiviert"),
+   ("exits_disabled", "Austrittssounds sind deaktiviert"),
+   ("no_exit_sound", "Kein Austrittssound für {user}"),
+   ("failed_get_user", "Der Benutzername konnte nicht abgerufen werden"),
+   ("no_mutual_servers", "Du hast keine gemeinsamen Server mit diesem Bot"),
+   ("more_clips", "...und {count} weitere"),
+   ("listed_clip_with_details", "- {clip} ({details})"),
+   ("clip_sample_rate", "{khz} kHz"),
+   ("clip_mono", "{khz} kHz Mono"),
+   ("clip_stereo", "{khz} kHz Stereo"),
+   ("clip_channels", "{khz} kHz mit {channels} Kanälen"),
+   ("clip_uploaded_by", "hochgeladen von {user}"),
+   ("search_outside_server", "Sounds können nur auf einem Server durchsucht werden"),
+   ("cannot_parse_search", "Die Suche konnte nicht gelesen werden"),
+   ("queue_outside_server", "Die Warteschlange kann nur auf einem Server angezeigt werden"),
+   (
+      "queue_disabled",
+      "Die Warteschlange ist auf diesem Server deaktiviert, Sounds werden sofort abgespielt",
+   ),
+   ("queue_empty", "Die Warteschlange ist leer"),
+   ("now_playing", "Läuft gerade: **{clip}**"),
+   ("volume_outside_server", "Die Lautstärke kann nur auf einem Server geändert werden"),
+   ("volume_out_of_range", "Die Lautstärke muss zwischen 0 % und {max} % liegen"),
+   ("volume", "Die Serverlautstärke ist {percent} %"),
+   ("volume_set", "Serverlautstärke auf {percent} % gesetzt"),
+   ("clip_volume", "{clip} spielt mit {percent} % der Serverlautstärke, die {server} % beträgt"),
+   ("clip_volume_set", "{clip} spielt jetzt mit {percent} % der Serverlautstärke"),
+   ("failed_save_volume", "Die Serverlautstärke konnte nicht gespeichert werden"),
+   ("failed_save_clip_volume", "Die Lautstärke für {clip} konnte nicht gespeichert werden"),
+   ("failed_read_clip_volume", "Die Lautstärke für {clip} konnte nicht gelesen werden"),
+   ("stats_outside_server", "Statistiken können nur auf einem Server angezeigt werden"),
+   ("failed_read_history", "Der Wiedergabeverlauf konnte nicht gelesen werden"),
+   ("nothing_played", "Hier wurde noch nichts abgespielt"),
+   (
+      "stats",
+      "{total} Wiedergaben insgesamt\n\n__Meistgespielte Sounds__\n{top_clips}\n\n__Aktivste Mitglieder__\n{top_users}\n\n__Letzte Wiedergaben__\n{recent}",
+   ),
+   ("recent_play", "- **{clip}** von {user} ({source}) <t:{timestamp}:R>"),
+   ("recent_play_anonymous", "- **{clip}** ({source}) <t:{timestamp}:R>"),
+   ("source_command", "Befehl"),
+   ("source_http", "Web"),
+   ("source_entrance", "Eintritt"),
+   ("source_exit", "Austritt"),
+   ("source_summon", "Herbeirufen"),
+   ("tags_outside_server", "Tags können nur auf einem Server geändert werden"),
+   ("cannot_parse_clip_name", "Der Name des Sounds konnte nicht gelesen werden"),
+   ("no_tags", "{clip} hat keine Tags"),
+   ("tagged", "{clip} hat die Tags {tags}"),
+   ("tags_set", "{clip} hat jetzt die Tags {tags}"),
+   ("tags_removed", "Die Tags von {clip} wurden entfernt"),
+   ("failed_find_clip", "{clip} konnte nicht gefunden werden"),
+   ("failed_read_tags", "Die Tags von {clip} konnten nicht gelesen werden"),
+   ("failed_save_tags", "Die Tags von {clip} konnten nicht gespeichert werden"),
+   ("upload_outside_server", "Sounds können nur auf einem Server hochgeladen werden"),
+   ("cannot_parse_upload", "Der Name des Sounds oder der Anhang konnte nicht gelesen werden"),
+   ("upload_too_large", "Sounds müssen kleiner als {mib} MiB sein"),
+   ("no_extension", "{file} hat keine Dateiendung, die sein Format verrät"),
+   ("failed_download", "Der Anhang konnte nicht heruntergeladen werden"),
+   ("not_playable", "{file} ist keine abspielbare Audiodatei"),
+   ("uploaded", "{clip} hochgeladen"),
+   ("upload_replaced", "{clip} ersetzt"),
+   ("failed_save_clip", "{clip} konnte nicht gespeichert werden"),
+   ("invalid_clip_name", "Namen von Sounds dürfen nicht leer sein oder Schrägstriche enthalten"),
+   ("unsupported_format", "Sounds müssen eines dieser Formate haben: {formats}"),
+   ("trim_outside_server", "Sounds können nur auf einem Server zugeschnitten werden"),
+   (
+      "nothing_to_trim",
+      "Gib einen Anfang, ein Ende oder strip_silence an, um den Sound zuzuschneiden",
+   ),
+   ("failed_read_clip", "{clip} konnte nicht gelesen werden"),
+   ("failed_trim", "{clip} konnte nicht zugeschnitten werden"),
+   (
+      "invalid_trim_range",
+      "Der Anfang muss vor dem Ende liegen, und keiner von beiden darf negativ sein",
+   ),
+   ("trim_past_end", "Der Sound ist nur {seconds} Sekunden lang"),
+   ("only_silence", "Von {clip} würde nur Stille übrig bleiben"),
+   ("trim_created", "{clip} aus {seconds} Sekunden von {source} erstellt"),
+   ("trim_replaced", "{clip} durch {seconds} Sekunden von {source} ersetzt"),
+   (
+      "token_issued",
+      "Dein API-Token ist `{token}`\nSende ihn als `Authorization: Bearer <token>`, um Sounds über den Webserver \
+       abzuspielen. Frühere Tokens funktionieren nicht mehr, und dieser wird nicht noch einmal angezeigt.",
+   ),
+   ("token_revoked", "Dein API-Token wurde widerrufen"),
+   ("no_token", "Du hast keinen API-Token"),
+   ("failed_create_token", "Der API-Token konnte nicht erstellt werden"),
+   ("failed_revoke_token", "Dein API-Token konnte nicht widerrufen werden"),
+   // slash commands
+   ("command.help.description", "Hilfe anzeigen"),
+   ("command.list.description", "Verfügbare Sounds auflisten"),
+   ("command.play.description", "Einen Sound aus der Bibliothek abspielen"),
+   ("command.play.name.description", "der Name des Sounds"),
+   ("command.random.description", "Einen zufälligen Sound aus der Bibliothek abspielen"),
+   (
+      "command.random.filter.description",
+      "nur Sounds auswählen, deren Name dies enthält",
+   ),
+   (
+      "command.search.description",
+      "Sounds nach Name oder Tag finden, auch mit Tippfehlern",
+   ),
+   ("command.search.query.description", "wonach gesucht wird"),
+   ("command.youtube.description", "Audio aus einem YouTube-Video abspielen"),
+   ("command.youtube.url.description", "die YouTube-URL"),
+   ("command.upload.description", "Einen Sound zur Bibliothek hinzufügen"),
+   (
+      "command.upload.file.description",
+      "die MP3-, OGG-, Opus-, WAV- oder FLAC-Datei zum Hochladen",
+   ),
+   ("command.upload.name.description", "der Name, unter dem der Sound abgespielt wird"),
+   (
+      "command.trim.description",
+      "Einen Sound aus einem Teil eines vorhandenen Sounds oder eines Uploads erstellen oder ersetzen",
+   ),
+   (
+      "command.trim.name.description",
+      "der zu schreibende Sound, der auch zugeschnitten wird, wenn keine andere Quelle angegeben ist",
+   ),
+   ("command.trim.start.description", "Sekunden in der Quelle, ab denen begonnen wird"),
+   ("command.trim.end.description", "Sekunden in der Quelle, bei denen aufgehört wird"),
+   (
+      "command.trim.strip_silence.description",
+      "auch die Stille am Anfang und Ende entfernen",
+   ),
+   ("command.trim.clip.description", "ein vorhandener Sound zum Zuschneiden"),
+   (
+      "command.trim.file.description",
+      "eine MP3-, OGG-, Opus-, WAV- oder FLAC-Datei zum Zuschneiden",
+   ),
+   (
+      "command.tags.description",
+      "Die Tags anzeigen oder ändern, über die /search einen Sound findet",
+   ),
+   ("command.tags.clip.description", "der Sound, der getaggt wird"),
+   (
+      "command.tags.tags.description",
+      "durch Kommas getrennte Tags, die die aktuellen ersetzen, oder -, um sie zu entfernen",
+   ),
+   ("command.summon.description", "Den Bot in deinen Sprachkanal holen"),
+   ("command.stop.description", "Die Wiedergabe des Bots stoppen"),
+   ("command.volume.description", "Die Wiedergabelautstärke anzeigen oder ändern"),
+   (
+      "command.volume.level.description",
+      "die neue Lautstärke in Prozent, von 0 bis 200",
+   ),
+   (
+      "command.volume.clip.description",
+      "ein Sound, der relativ zur Serverlautstärke angepasst wird",
+   ),
+   (
+      "command.token.description",
+      "Einen privaten Token zum Abspielen von Sounds über den Webserver erhalten",
+   ),
+   (
+      "command.token.revoke.description",
+      "deinen Token widerrufen, statt ihn zu ersetzen",
+   ),
+   ("command.queue.description", "Die Sounds anzeigen, die auf ihre Wiedergabe warten"),
+   (
+      "command.stats.description",
+      "Die meistgespielten Sounds, die aktivsten Mitglieder und die letzten Wiedergaben anzeigen",
+   ),
+   ("command.skip.description", "Zum nächsten Sound in der Warteschlange springen"),
+];
@@ -0,0 +1,142 @@
+// Every message the bot shows, which other catalogs translate and fall back to for anything they leave out
+pub const MESSAGES: &[(&str, &str)] = &[
+   (
+      "help",
+      "You can type any of the following commands:
+```
+/list    - Returns a list of available sound files.
+/play    - Plays the specified sound from the list.
+/search  - Finds sounds by name or tag, with buttons to play them.
+/random  - Plays a random sound, optionally one whose name contains a filter.
+/youtube - Plays the youtube link specified.
+/upload  - Adds an audio attachment to the sound files (Sound Clip Admins only).
+/trim    - Cuts a sound or an attachment down to a range or strips its silence (Sound Clip Admins only).
+/stop    - Stops the currently playing sound(s).
+/queue   - Shows the sounds waiting to be played.
+/volume  - Shows or changes the server volume, or one sound's volume (changing it is for Sound Clip Admins only).
+/skip    - Skips to the next sound in the queue.
+/stats   - Shows the most played sounds, top members and recent plays.
+/tags    - Shows or changes the tags a sound can be searched by (changing them is for Sound Clip Admins only).
+/summon  - Summon the bot to your current voice channel.
+/token   - Gives you a private token for playing sounds through the web server.
+```",
+   ),
+   ("result_title", "/{command} result"),
+   ("unrecognized_command", "Unrecognized command!"),
+   // why a call didn't go through
+   ("clip_not_found", "Audio file not found for {clip}"),
+   ("not_in_voice_channel", "You are not in a voice channel!"),
+   ("wrong_channel", "Bot is not currently in your channel"),
+   ("join_failed", "Bot failed to join your channel"),
+   ("shutting_down", "The bot is shutting down, try again once it's back"),
+   ("admin_required", "You need the Sound Clip Admin role to use /{command} in this server"),
+   ("command_outside_server", "/{command} can only be used from within a server"),
+   ("rate_limited", "You're playing clips too quickly, try again in {seconds} seconds"),
+   ("rate_limited_one", "You're playing clips too quickly, try again in 1 second"),
+   // playing
+   ("playing", "Playing {clip}"),
+   ("playing_with_details", "Playing {clip} ({details})"),
+   ("queued", "Queued {clip}"),
+   ("queued_with_details", "Queued {clip} ({details})"),
+   ("cannot_parse_file_name", "Cannot parse file name"),
+   ("cannot_parse_url", "Cannot parse YouTube URL"),
+   ("invalid_url", "{url} is not a valid URL"),
+   ("failed_load_clip", "Failed to load file for {clip}"),
+   ("no_clips", "There are no sound files to play"),
+   ("no_matching_clips", "No sound files match {query}"),
+   ("failed_list_clips", "Failed to list sound files"),
+   ("summoned", "Bot summoned"),
+   ("playback_stopped", "Playback stopped"),
+   ("skipped", "Skipped {clip}"),
+   ("failed_skip", "Failed to skip the current clip"),
+   ("nothing_queued", "Nothing is queued"),
+   // entrances and exits, which are only logged
+   ("bot_joined", "A bot joined a channel: {user}"),
+   ("bot_left", "A bot left a channel: {user}"),
+   ("entrances_disabled", "Entrance sounds are disabled"),
+   ("exits_disabled", "Exit sounds are disabled"),
+   ("no_exit_sound", "No exit sound for {user}"),
+   ("failed_get_user", "Could not get user name"),
+   // listing
+   ("no_mutual_servers", "You have no mutual servers with this bot"),
+   ("more_clips", "...and {count} more"),
+   ("listed_clip_with_details", "- {clip} ({details})"),
+   ("clip_sample_rate", "{khz} kHz"),
+   ("clip_mono", "{khz} kHz mono"),
+   ("clip_stereo", "{khz} kHz stereo"),
+   ("clip_channels", "{khz} kHz {channels} channels"),
+   ("clip_uploaded_by", "uploaded by {user}"),
+   ("search_outside_server", "Sound files can only be searched from within a server"),
+   ("cannot_parse_search", "Cannot parse the search"),
+   // the queue
+   ("queue_outside_server", "The queue can only be viewed from within a server"),
+   ("queue_disabled", "The queue is disabled in this server, clips play as soon as they are requested"),
+   ("queue_empty", "The queue is empty"),
+   ("now_playing", "Now playing: **{clip}**"),
+   // volume
+   ("volume_outside_server", "Volume can only be changed from within a server"),
+   ("volume_out_of_range", "Volume must be between 0% and {max}%"),
+   ("volume", "Server volume is {percent}%"),
+   ("volume_set", "Server volume set to {percent}%"),
+   ("clip_volume", "{clip} plays at {percent}% of the server volume, which is {server}%"),
+   ("clip_volume_set", "{clip} now plays at {percent}% of the server volume"),
+   ("failed_save_volume", "Failed to save the server volume"),
+   ("failed_save_clip_volume", "Failed to save the volume for {clip}"),
+   ("failed_read_clip_volume", "Failed to read the volume for {clip}"),
+   // stats
+   ("stats_outside_server", "Stats can only be viewed from within a server"),
+   ("failed_read_history", "Failed to read the play history"),
+   ("nothing_played", "Nothing has been played here yet"),
+   (
+      "stats",
+      "{total} plays in total\n\n__Top sound files__\n{top_clips}\n\n__Top members__\n{top_users}\n\n__Recent plays__\n{recent}",
+   ),
+   ("recent_play", "- **{clip}** by {user} ({source}) <t:{timestamp}:R>"),
+   ("recent_play_anonymous", "- **{clip}** ({source}) <t:{timestamp}:R>"),
+   ("source_command", "command"),
+   ("source_http", "web"),
+   ("source_entrance", "entrance"),
+   ("source_exit", "exit"),
+   ("source_summon", "summon"),
+   // tags
+   ("tags_outside_server", "Tags can only be changed from within a server"),
+   ("cannot_parse_clip_name", "Cannot parse the clip name"),
+   ("no_tags", "{clip} has no tags"),
+   ("tagged", "{clip} is tagged {tags}"),
+   ("tags_set", "{clip} is now tagged {tags}"),
+   ("tags_removed", "Removed the tags from {clip}"),
+   ("failed_find_clip", "Failed to find {clip}"),
+   ("failed_read_tags", "Failed to read the tags for {clip}"),
+   ("failed_save_tags", "Failed to save the tags for {clip}"),
+   // uploading and trimming
+   ("upload_outside_server", "Clips can only be uploaded from within a server"),
+   ("cannot_parse_upload", "Cannot parse the clip name or attachment"),
+   ("upload_too_large", "Clips must be smaller than {mib} MiB"),
+   ("no_extension", "{file} has no file extension to tell its format"),
+   ("failed_download", "Failed to download the attachment"),
+   ("not_playable", "{file} is not a playable audio file"),
+   ("uploaded", "Uploaded {clip}"),
+   ("upload_replaced", "Replaced {clip}"),
+   ("failed_save_clip", "Failed to save {clip}"),
+   ("invalid_clip_name", "Clip names cannot be empty or contain slashes"),
+   ("unsupported_format", "Clips must be one of: {formats}"),
+   ("trim_outside_server", "Clips can only be trimmed from within a server"),
+   ("nothing_to_trim", "Give a start, an end or strip_silence to trim the clip by"),
+   ("failed_read_clip", "Failed to read {clip}"),
+   ("failed_trim", "Failed to trim {clip}"),
+   ("invalid_trim_range", "The start must come before the end, and neither can be negative"),
+   ("trim_past_end", "The clip is only {seconds} seconds long"),
+   ("only_silence", "Nothing but silence would be left of {clip}"),
+   ("trim_created", "Created {clip} with {seconds} seconds of {source}"),
+   ("trim_replaced", "Replaced {clip} with {seconds} seconds of {source}"),
+   // API tokens
+   (
+      "token_issued",
+      "Your API token is `{token}`\nSend it as `Authorization: Bearer <token>` to play clips through the web server. \
+       Any token you had before no longer works, and this one won't be shown again.",
+   ),
+   ("token_revoked", "Your API token has been revoked"),
+   ("no_token", "You don't have an API token"),
+   ("failed_create_token", "Failed to create an API token"),
+   ("failed_revoke_token", "Failed to revoke your API token"),
+];
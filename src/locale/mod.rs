@@ -0,0 +1,220 @@
+use std::fmt;
+
+mod de;
+mod en;
+mod pt_br;
+
+/// A language the bot can answer in, each with its own catalog of messages.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Locale {
+   #[default]
+   English,
+   German,
+   /// Brazilian Portuguese
+   Portuguese,
+}
+
+impl Locale {
+   pub const ALL: [Locale; 3] = [Locale::English, Locale::German, Locale::Portuguese];
+
+   /// The locale for a Discord client's language, which is English for languages without a catalog.
+   pub fn from_discord(code: &str) -> Self {
+      match code {
+         "de" => Locale::German,
+         "pt-BR" => Locale::Portuguese,
+         _ => Locale::English,
+      }
+   }
+
+   /// The code Discord knows the locale by.
+   pub fn code(self) -> &'static str {
+      match self {
+         Locale::English => "en-US",
+         Locale::German => "de",
+         Locale::Portuguese => "pt-BR",
+      }
+   }
+
+   /// The locale's text for a catalog entry, if it has one.
+   pub fn lookup(self, key: &str) -> Option<&'static str> {
+      self
+         .catalog()
+         .iter()
+         .find(|(entry, _)| *entry == key)
+         .map(|(_, text)| *text)
+   }
+
+   /// Every entry the locale has text for, by key.
+   pub fn catalog(self) -> &'static [(&'static str, &'static str)] {
+      match self {
+         Locale::English => en::MESSAGES,
+         Locale::German => de::MESSAGES,
+         Locale::Portuguese => pt_br::MESSAGES,
+      }
+   }
+}
+
+/// Text for a user, kept as catalog entries until it's known which language to show it in.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Message {
+   /// An entry of the catalog, along with what to fill its `{placeholders}` with
+   Entry {
+      key: &'static str,
+      args: Vec<(&'static str, Message)>,
+   },
+   /// Text that reads the same in every language, like clip names
+   Plain(String),
+   /// Messages shown one per line
+   Lines(Vec<Message>),
+   /// Messages shown one after another, separated by commas
+   List(Vec<Message>),
+}
+
+impl Message {
+   pub fn new(key: &'static str) -> Self {
+      Message::Entry { key, args: Vec::new() }
+   }
+
+   /// Fills the entry's `{name}` placeholder with a value shown as is.
+   pub fn with<T: ToString>(self, name: &'static str, value: T) -> Self {
+      self.with_message(name, Message::Plain(value.to_string()))
+   }
+
+   /// Fills the entry's `{name}` placeholder with another message, shown in the same language.
+   pub fn with_message(mut self, name: &'static str, value: Message) -> Self {
+      if let Message::Entry { args, .. } = &mut self {
+         args.push((name, value));
+      }
+      self
+   }
+
+   /// The message in the locale's language, using English for any entry its catalog leaves out.
+   pub fn text(&self, locale: Locale) -> String {
+      match self {
+         Message::Entry { key, args } => {
+            let template = locale
+               .lookup(key)
+               .or_else(|| Locale::English.lookup(key))
+               .unwrap_or(key);
+            fill(template, |name| {
+               args
+                  .iter()
+                  .find(|(arg, _)| *arg == name)
+                  .map(|(_, value)| value.text(locale))
+            })
+         }
+         Message::Plain(text) => text.clone(),
+         Message::Lines(lines) => lines
+            .iter()
+            .map(|line| line.text(locale))
+            .collect::<Vec<_>>()
+            .join("\n"),
+         Message::List(items) => items
+            .iter()
+            .map(|item| item.text(locale))
+            .collect::<Vec<_>>()
+            .join(", "),
+      }
+   }
+}
+
+impl fmt::Display for Message {
+   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+      f.write_str(&self.text(Locale::English))
+   }
+}
+
+/// Replaces each `{name}` in the template in a single pass, so filled in values are never filled in again. Unknown
+/// placeholders are left as they are.
+fn fill<F: Fn(&str) -> Option<String>>(template: &str, value: F) -> String {
+   let mut text = String::with_capacity(template.len());
+   let mut rest = template;
+   while let Some(start) = rest.find('{') {
+      text.push_str(&rest[..start]);
+      let placeholder = &rest[start..];
+      match placeholder
+         .find('}')
+         .and_then(|end| Some((end, value(&placeholder[1..end])?)))
+      {
+         Some((end, filled)) => {
+            text.push_str(&filled);
+            rest = &placeholder[end + 1..];
+         }
+         None => {
+            text.push('{');
+            rest = &placeholder[1..];
+         }
+      }
+   }
+   text.push_str(rest);
+   text
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+   use std::collections::BTreeSet;
+
+   fn placeholders(text: &str) -> BTreeSet<&str> {
+      text
+         .split('{')
+         .skip(1)
+         .filter_map(|part| part.split_once('}').map(|(name, _)| name))
+         .collect()
+   }
+
+   #[test]
+   fn test_translations_match_english() {
+      for locale in &Locale::ALL[1..] {
+         for (key, english) in en::MESSAGES {
+            let translated = locale.lookup(key);
+            assert!(translated.is_some(), "{locale:?} has no translation of {key}");
+            assert_eq!(
+               placeholders(translated.unwrap()),
+               placeholders(english),
+               "{locale:?} fills in different values for {key}"
+            );
+         }
+         // command descriptions are written next to the commands, so only those may be missing from English
+         for (key, _) in locale.catalog() {
+            assert!(
+               key.starts_with("command.") || Locale::English.lookup(key).is_some(),
+               "{locale:?} translates {key}, which English doesn't have"
+            );
+         }
+      }
+   }
+
+   #[test]
+   fn test_unknown_locales_fall_back_to_english() {
+      let message = Message::new("skipped").with("clip", "airhorn");
+
+      assert_eq!(message.text(Locale::from_discord("de")), "airhorn übersprungen");
+      assert_eq!(message.text(Locale::from_discord("fr")), "Skipped airhorn");
+      assert_eq!(message.to_string(), "Skipped airhorn");
+   }
+
+   #[test]
+   fn test_messages_filled_in() {
+      let message = Message::new("recent_play")
+         .with("clip", "{user}")
+         .with("user", "<@7>")
+         .with_message("source", Message::new("source_command"))
+         .with("timestamp", 1);
+
+      assert_eq!(message.text(Locale::English), "- **{user}** by <@7> (command) <t:1:R>");
+      assert_eq!(message.text(Locale::German), "- **{user}** von <@7> (Befehl) <t:1:R>");
+      assert_eq!(
+         Message::Lines(vec![Message::new("queue_empty"), Message::Plain("{x}".to_string())]).text(Locale::English),
+         "The queue is empty\n{x}"
+      );
+      assert_eq!(
+         Message::List(vec![
+            Message::Plain("1s".to_string()),
+            Message::new("clip_stereo").with("khz", 48)
+         ])
+         .text(Locale::Portuguese),
+         "1s, 48 kHz estéreo"
+      );
+   }
+}
@@ -0,0 +1,211 @@
+// Command names are left as they are so /help, the README and `admin_commands` use the same names in every language
+pub const MESSAGES: &[(&str, &str)] = &[
+   (
+      "help",
+      "Você pode usar qualquer um dos comandos a seguir:
+```
+/list    - Mostra a lista de sons disponíveis.
+/play    - Toca o som indicado da lista.
+/search  - Encontra sons pelo nome ou por tag, com botões para tocá-los.
+/random  - Toca um som aleatório, opcionalmente um cujo nome contenha um filtro.
+/youtube - Toca o link do YouTube indicado.
+/upload  - Adiciona um anexo de áudio aos sons (somente Sound Clip Admins).
+/trim    - Corta um som ou anexo para um intervalo ou remove seu silêncio (somente Sound Clip Admins).
+/stop    - Para os sons que estão tocando.
+/queue   - Mostra os sons esperando para tocar.
+/volume  - Mostra ou muda o volume do servidor, ou o volume de um som (mudar é somente para Sound Clip Admins).
+/skip    - Pula para o próximo som da fila.
+/stats   - Mostra os sons mais tocados, os membros que mais tocam e as reproduções recentes.
+/tags    - Mostra ou muda as tags pelas quais um som pode ser buscado (mudar é somente para Sound Clip Admins).
+/summon  - Chama o bot para o seu canal de voz atual.
+/token   - Dá a você um token privado para tocar sons pelo servidor web.
+```",
+   ),
+   ("result_title", "Resultado de /{command}"),
+   ("unrecognized_command", "Comando desconhecido!"),
+   ("clip_not_found", "Nenhum arquivo de áudio encontrado para {clip}"),
+   ("not_in_voice_channel", "Você não está em um canal de voz!"),
+   ("wrong_channel", "O bot não está no seu canal agora"),
+   ("join_failed", "O bot não conseguiu entrar no seu canal"),
+   ("shutting_down", "O bot está desligando, tente de novo quando ele voltar"),
+   ("admin_required", "Você precisa do cargo Sound Clip Admin para usar /{command} neste servidor"),
+   ("command_outside_server", "/{command} só pode ser usado dentro de um servidor"),
+   ("rate_limited", "Você está tocando sons rápido demais, tente de novo em {seconds} segundos"),
+   ("rate_limited_one", "Você está tocando sons rápido demais, tente de novo em 1 segundo"),
+   ("playing", "Tocando {clip}"),
+   ("playing_with_details", "Tocando {clip} ({details})"),
+   ("queued", "{clip} entrou na fila"),
+   ("queued_with_details", "{clip} entrou na fila ({details})"),
+   ("cannot_parse_file_name", "Não foi possível ler o nome do arquivo"),
+   ("cannot_parse_url", "Não foi possível ler a URL do YouTube"),
+   ("invalid_url", "{url} não é uma URL válida"),
+   ("failed_load_clip", "Falha ao carregar o arquivo de {clip}"),
+   ("no_clips", "Não há sons para tocar"),
+   ("no_matching_clips", "Nenhum som corresponde a {query}"),
+   ("failed_list_clips", "Falha ao listar os sons"),
+   ("summoned", "Bot chamado"),
+   ("playback_stopped", "Reprodução parada"),
+   ("skipped", "{clip} pulado"),
+   ("failed_skip", "Falha ao pular o som atual"),
+   ("nothing_queued", "Não há nada na fila"),
+   ("bot_joined", "Um bot entrou em um canal: {user}"),
+   ("bot_left", "Um bot saiu de um canal: {user}"),
+   ("entrances_disabled", "Os sons de entrada estão desativados"),
+   ("exits_disabled", "Os sons de saída estão desativados"),
+   ("no_exit_sound", "Nenhum som de saída para {user}"),
+   ("failed_get_user", "Não foi possível obter o nome do usuário"),
+   ("no_mutual_servers", "Você não tem servidores em comum com este bot"),
+   ("more_clips", "...e mais {count}"),
+   ("listed_clip_with_details", "- {clip} ({details})"),
+   ("clip_sample_rate", "{khz} kHz"),
+   ("clip_mono", "{khz} kHz mono"),
+   ("clip_stereo", "{khz} kHz estéreo"),
+   ("clip_channels", "{khz} kHz com {channels} canais"),
+   ("clip_uploaded_by", "enviado por {user}"),
+   ("search_outside_server", "Só é possível buscar sons dentro de um servidor"),
+   ("cannot_parse_search", "Não foi possível ler a busca"),
+   ("queue_outside_server", "A fila só pode ser vista dentro de um servidor"),
+   (
+      "queue_disabled",
+      "A fila está desativada neste servidor, os sons tocam assim que são pedidos",
+   ),
+   ("queue_empty", "A fila está vazia"),
+   ("now_playing", "Tocando agora: **{clip}**"),
+   ("volume_outside_server", "O volume só pode ser alterado dentro de um servidor"),
+   ("volume_out_of_range", "O volume deve estar entre 0% e {max}%"),
+   ("volume", "O volume do servidor é {percent}%"),
+   ("volume_set", "Volume do servidor definido como {percent}%"),
+   ("clip_volume", "{clip} toca a {percent}% do volume do servidor, que é {server}%"),
+   ("clip_volume_set", "{clip} agora toca a {percent}% do volume do servidor"),
+   ("failed_save_volume", "Falha ao salvar o volume do servidor"),
+   ("failed_save_clip_volume", "Falha ao salvar o volume de {clip}"),
+   ("failed_read_clip_volume", "Falha ao ler o volume de {clip}"),
+   ("stats_outside_server", "As estatísticas só podem ser vistas dentro de um servidor"),
+   ("failed_read_history", "Falha ao ler o histórico de reproduções"),
+   ("nothing_played", "Nada foi tocado aqui ainda"),
+   (
+      "stats",
+      "{total} reproduções no total\n\n__Sons mais tocados__\n{top_clips}\n\n__Membros que mais tocam__\n{top_users}\n\n__Reproduções recentes__\n{recent}",
+   ),
+   ("recent_play", "- **{clip}** por {user} ({source}) <t:{timestamp}:R>"),
+   ("recent_play_anonymous", "- **{clip}** ({source}) <t:{timestamp}:R>"),
+   ("source_command", "comando"),
+   ("source_http", "web"),
+   ("source_entrance", "entrada"),
+   ("source_exit", "saída"),
+   ("source_summon", "chamada"),
+   ("tags_outside_server", "As tags só podem ser alteradas dentro de um servidor"),
+   ("cannot_parse_clip_name", "Não foi possível ler o nome do som"),
+   ("no_tags", "{clip} não tem tags"),
+   ("tagged", "{clip} tem as tags {tags}"),
+   ("tags_set", "{clip} agora tem as tags {tags}"),
+   ("tags_removed", "As tags de {clip} foram removidas"),
+   ("failed_find_clip", "Falha ao encontrar {clip}"),
+   ("failed_read_tags", "Falha ao ler as tags de {clip}"),
+   ("failed_save_tags", "Falha ao salvar as tags de {clip}"),
+   ("upload_outside_server", "Só é possível enviar sons dentro de um servidor"),
+   ("cannot_parse_upload", "Não foi possível ler o nome do som ou o anexo"),
+   ("upload_too_large", "Os sons devem ter menos de {mib} MiB"),
+   ("no_extension", "{file} não tem uma extensão que indique seu formato"),
+   ("failed_download", "Falha ao baixar o anexo"),
+   ("not_playable", "{file} não é um arquivo de áudio reproduzível"),
+   ("uploaded", "{clip} enviado"),
+   ("upload_replaced", "{clip} substituído"),
+   ("failed_save_clip", "Falha ao salvar {clip}"),
+   ("invalid_clip_name", "Os nomes dos sons não podem ser vazios nem conter barras"),
+   ("unsupported_format", "Os sons devem estar em um destes formatos: {formats}"),
+   ("trim_outside_server", "Só é possível cortar sons dentro de um servidor"),
+   ("nothing_to_trim", "Informe um início, um fim ou strip_silence para cortar o som"),
+   ("failed_read_clip", "Falha ao ler {clip}"),
+   ("failed_trim", "Falha ao cortar {clip}"),
+   ("invalid_trim_range", "O início deve vir antes do fim, e nenhum dos dois pode ser negativo"),
+   ("trim_past_end", "O som tem só {seconds} segundos"),
+   ("only_silence", "Só restaria silêncio de {clip}"),
+   ("trim_created", "{clip} criado com {seconds} segundos de {source}"),
+   ("trim_replaced", "{clip} substituído por {seconds} segundos de {source}"),
+   (
+      "token_issued",
+      "Seu token de API é `{token}`\nEnvie-o como `Authorization: Bearer <token>` para tocar sons pelo servidor web. \
+       Qualquer token que você tinha antes deixou de funcionar, e este não será mostrado de novo.",
+   ),
+   ("token_revoked", "Seu token de API foi revogado"),
+   ("no_token", "Você não tem um token de API"),
+   ("failed_create_token", "Falha ao criar um token de API"),
+   ("failed_revoke_token", "Falha ao revogar seu token de API"),
+   // slash commands
+   ("command.help.description", "Mostrar informações de ajuda"),
+   ("command.list.description", "Listar os sons disponíveis"),
+   ("command.play.description", "Tocar um som da biblioteca"),
+   ("command.play.name.description", "o nome do som"),
+   ("command.random.description", "Tocar um som aleatório da biblioteca"),
+   (
+      "command.random.filter.description",
+      "escolher apenas sons cujo nome contenha isto",
+   ),
+   (
+      "command.search.description",
+      "Encontrar sons pelo nome ou por tag, mesmo com erros de digitação",
+   ),
+   ("command.search.query.description", "o que procurar"),
+   ("command.youtube.description", "Tocar o áudio de um vídeo do YouTube"),
+   ("command.youtube.url.description", "a URL do YouTube"),
+   ("command.upload.description", "Adicionar um som à biblioteca"),
+   (
+      "command.upload.file.description",
+      "o arquivo MP3, OGG, Opus, WAV ou FLAC a enviar",
+   ),
+   ("command.upload.name.description", "o nome pelo qual o som será tocado"),
+   (
+      "command.trim.description",
+      "Criar ou substituir um som a partir de parte de um som existente ou de um envio",
+   ),
+   (
+      "command.trim.name.description",
+      "o som a escrever, que também é o cortado se nenhuma outra fonte for informada",
+   ),
+   ("command.trim.start.description", "segundos da fonte a partir dos quais começar"),
+   ("command.trim.end.description", "segundos da fonte em que parar"),
+   (
+      "command.trim.strip_silence.description",
+      "também cortar o silêncio do início e do fim",
+   ),
+   ("command.trim.clip.description", "um som existente a cortar"),
+   (
+      "command.trim.file.description",
+      "um arquivo MP3, OGG, Opus, WAV ou FLAC a cortar",
+   ),
+   (
+      "command.tags.description",
+      "Mostrar ou mudar as tags pelas quais o /search encontra um som",
+   ),
+   ("command.tags.clip.description", "o som a marcar"),
+   (
+      "command.tags.tags.description",
+      "tags separadas por vírgula para substituir as atuais, ou - para removê-las",
+   ),
+   ("command.summon.description", "Chamar o bot para o seu canal de voz"),
+   ("command.stop.description", "Parar a reprodução do bot"),
+   ("command.volume.description", "Mostrar ou mudar o volume de reprodução"),
+   (
+      "command.volume.level.description",
+      "o novo volume em porcentagem, de 0 a 200",
+   ),
+   (
+      "command.volume.clip.description",
+      "um som a ajustar em relação ao volume do servidor",
+   ),
+   (
+      "command.token.description",
+      "Receber um token privado para tocar sons pelo servidor web",
+   ),
+   (
+      "command.token.revoke.description",
+      "revogar seu token em vez de substituí-lo",
+   ),
+   ("command.queue.description", "Mostrar os sons esperando para tocar"),
+   (
+      "command.stats.description",
+      "Mostrar os sons mais tocados, os membros que mais tocam e as reproduções recentes",
+   ),
+   ("command.skip.description", "Pular para o próximo som da fila"),
+];
@@ -1,64 +1,142 @@
 mod actions;
+mod api_tokens;
 mod audio;
+mod autocomplete;
 mod call_result;
 mod chat;
 mod commands;
+mod config;
+mod dashboard;
 mod event;
+mod guild_config;
 mod guilds;
+mod history;
 mod http;
+mod locale;
+mod permissions;
+mod rate_limit;
 mod role;
+mod search;
+mod shutdown;
 
+use api_tokens::ApiTokens;
+use audio::{audio_source, library, random::RecentPicks, tracks::PlayingTracks};
+use config::Config;
 use event::listener::SoundboardListener;
-use log::error;
+use guild_config::GuildConfigs;
+use log::{error, info};
+use rate_limit::RateLimiter;
 use rocket::{catchers, routes};
 use serenity::{cache::Cache, client::Client, http::Http, prelude::GatewayIntents};
 use songbird::{SerenityInit, Songbird, SongbirdKey};
-use std::{env, sync::Arc};
+use std::{process, sync::Arc};
 
 pub struct WebContext {
    pub cache: Arc<Cache>,
    pub http: Arc<Http>,
    pub songbird: Arc<Songbird>,
+   pub configs: Arc<GuildConfigs>,
+   pub tracks: Arc<PlayingTracks>,
+   pub rate_limiter: Arc<RateLimiter>,
+   pub tokens: Arc<ApiTokens>,
 }
 
 #[rocket::main]
 async fn main() {
    env_logger::init();
 
-   let token = env::var("DISCORD_TOKEN").expect("Expected a token in the environment");
-   let application_id: u64 = env::var("APPLICATION_ID")
-      .expect("Expected an app id in the environment")
-      .parse()
-      .expect("A valid numerical ID");
-   env::var("WEB_URI").expect("Expected a web URI in the environment");
+   let config = Arc::new(Config::load().unwrap_or_else(|problems| {
+      for problem in problems {
+         eprintln!("{problem}");
+      }
+      process::exit(2)
+   }));
+   audio_source::set_file_dir(config.audio_file_dir.clone());
+   // clips are listed from the directory each time they are needed if it can't be watched
+   let _watcher = library::watch(&config.audio_file_dir)
+      .map_err(|err| {
+         error!(
+            "Could not watch {:?} for clip changes: {:?}",
+            config.audio_file_dir, err
+         )
+      })
+      .ok();
+   let configs = Arc::new(GuildConfigs::default());
+   let tracks = Arc::new(PlayingTracks::default());
+   let rate_limiter = Arc::new(RateLimiter::default());
+   let tokens = Arc::new(ApiTokens::load(&config.audio_file_dir).expect("Could not read the API tokens"));
 
-   let mut client = Client::builder(token, GatewayIntents::GUILDS | GatewayIntents::GUILD_VOICE_STATES)
-      .application_id(application_id.into())
-      .event_handler(SoundboardListener::new())
-      .register_songbird()
-      .await
-      .expect("Err creating client");
+   let mut client = Client::builder(
+      &config.discord_token,
+      GatewayIntents::GUILDS | GatewayIntents::GUILD_VOICE_STATES,
+   )
+   .application_id(config.application_id)
+   .event_handler(SoundboardListener::new())
+   .register_songbird()
+   .type_map_insert::<Config>(config.clone())
+   .type_map_insert::<GuildConfigs>(configs.clone())
+   .type_map_insert::<PlayingTracks>(tracks.clone())
+   .type_map_insert::<RateLimiter>(rate_limiter.clone())
+   .type_map_insert::<ApiTokens>(tokens.clone())
+   .type_map_insert::<RecentPicks>(Arc::new(RecentPicks::default()))
+   .await
+   .expect("Err creating client");
 
-   let rocket = rocket::build()
-      .mount("/", routes![http::play])
+   // shutdown is coordinated below so the bot can leave voice before the web server stops
+   let figment = rocket::Config::figment()
+      .merge(("shutdown.ctrlc", false))
+      .merge(("shutdown.signals", Vec::<String>::new()));
+   let songbird = client
+      .data
+      .read()
+      .await
+      .get::<SongbirdKey>()
+      .cloned()
+      .expect("Songbird should be registered!");
+   let rocket = rocket::custom(figment)
+      .mount(
+         "/",
+         routes![
+            http::play,
+            http::stop,
+            http::playing,
+            http::clips_json,
+            http::clips_html,
+            dashboard::page,
+            dashboard::login,
+            dashboard::logout,
+         ],
+      )
       .register("/", catchers![http::default_catcher])
+      .manage(config.clone())
       .manage(WebContext {
          cache: client.cache.clone(),
          http: client.http.clone(),
-         songbird: client
-            .data
-            .read()
-            .await
-            .get::<SongbirdKey>()
-            .cloned()
-            .expect("Songbird should be registered!"),
+         songbird: songbird.clone(),
+         configs,
+         tracks: tracks.clone(),
+         rate_limiter,
+         tokens,
       });
 
+   let rocket = match rocket.ignite().await {
+      Ok(rocket) => rocket,
+      Err(err) => return error!("Webserver could not start: {:?}", err),
+   };
+   let web_shutdown = rocket.shutdown();
+   let shard_manager = client.shard_manager.clone();
+
    tokio::spawn(async move {
       if let Err(err) = client.start().await {
          error!("Client ended: {:?}", err)
       }
    });
+   tokio::spawn(async move {
+      shutdown::signal().await;
+      info!("Shutting down");
+      shutdown::run(&songbird, &tracks, &shard_manager, config.shutdown_grace).await;
+      web_shutdown.notify();
+   });
    if let Err(err) = rocket.launch().await {
       error!("Webserver ended: {:?}", err)
    }
@@ -0,0 +1,101 @@
+use serenity::{
+   client::Context,
+   model::{application::CommandInteraction, guild::Member, id::GuildId},
+};
+
+use crate::{
+   guild_config::{self, GuildConfig},
+   locale::Message,
+   role,
+};
+
+/// Commands that change the clip library, which always require the Sound Clip Admin role.
+const CLIP_MANAGEMENT_COMMANDS: [&str; 2] = ["upload", "trim"];
+
+/// Commands that change a guild or clip setting when given the paired option, which then requires the Sound Clip Admin
+/// role. Without it they only show the setting, which anyone may do.
+const SETTING_OPTIONS: [(&str, &str); 2] = [("volume", "level"), ("tags", "tags")];
+
+/// Checks whether the invoking member may run the command, returning the message to show them if not.
+pub async fn check(ctx: &Context, command: &CommandInteraction) -> Result<(), Message> {
+   let name = &command.data.name;
+   let member = command.member.as_deref();
+   let options = command.data.options.iter().map(|option| option.name.as_str());
+   if changes_setting(name, options) {
+      return admin_only(name, command.guild_id, member);
+   }
+   check_member(ctx, name, command.guild_id, member).await
+}
+
+/// Checks whether a member may do what the named command does, such as when they press a button that plays a clip.
+pub async fn check_member(
+   ctx: &Context, name: &str, guild_id: Option<GuildId>, member: Option<&Member>,
+) -> Result<(), Message> {
+   let config = match guild_id {
+      Some(guild_id) => guild_config::get(ctx).await.get(guild_id).await,
+      None => GuildConfig::default(),
+   };
+   check_with_config(&config, name, guild_id, member)
+}
+
+/// Checks a member against an already loaded guild configuration, such as from the web server.
+pub fn check_with_config(
+   config: &GuildConfig, name: &str, guild_id: Option<GuildId>, member: Option<&Member>,
+) -> Result<(), Message> {
+   if !requires_admin(name, config) {
+      return Ok(());
+   }
+   admin_only(name, guild_id, member)
+}
+
+fn admin_only(name: &str, guild_id: Option<GuildId>, member: Option<&Member>) -> Result<(), Message> {
+   match (guild_id, member) {
+      (Some(guild_id), Some(member)) if role::is_admin(&guild_id, member) => Ok(()),
+      (Some(_), Some(_)) => Err(Message::new("admin_required").with("command", name)),
+      _ => Err(Message::new("command_outside_server").with("command", name)),
+   }
+}
+
+fn requires_admin(name: &str, config: &GuildConfig) -> bool {
+   CLIP_MANAGEMENT_COMMANDS.contains(&name) || config.admin_commands.iter().any(|command| command == name)
+}
+
+fn changes_setting<'a, I: IntoIterator<Item = &'a str>>(name: &str, options: I) -> bool {
+   let options = options.into_iter().collect::<Vec<_>>();
+   SETTING_OPTIONS
+      .iter()
+      .any(|(command, option)| *command == name && options.contains(option))
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   #[test]
+   fn test_configured_commands_require_admin() {
+      let config = GuildConfig {
+         admin_commands: vec!["stop".to_string(), "summon".to_string()],
+         ..Default::default()
+      };
+
+      assert!(requires_admin("stop", &config));
+      assert!(requires_admin("summon", &config));
+      assert!(!requires_admin("play", &config));
+   }
+
+   #[test]
+   fn test_clip_management_always_requires_admin() {
+      assert!(requires_admin("upload", &GuildConfig::default()));
+      assert!(!requires_admin("stop", &GuildConfig::default()));
+   }
+
+   #[test]
+   fn test_changing_settings_requires_admin() {
+      assert!(changes_setting("volume", ["level"]));
+      assert!(changes_setting("volume", ["clip", "level"]));
+      assert!(!changes_setting("volume", ["clip"]));
+      assert!(changes_setting("tags", ["clip", "tags"]));
+      assert!(!changes_setting("tags", ["clip"]));
+      assert!(!changes_setting("play", ["level"]));
+   }
+}
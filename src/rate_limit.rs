@@ -0,0 +1,258 @@
+use serde::{Deserialize, Serialize};
+use serenity::{
+   client::Context,
+   model::id::{GuildId, UserId},
+   prelude::TypeMapKey,
+};
+use std::{
+   collections::HashMap,
+   sync::{Arc, Mutex},
+   time::{Duration, Instant},
+};
+
+use crate::locale::Message;
+
+/// Token bucket sizes for a guild, configured under `[rate_limit]` in its `config.toml`.
+///
+/// A limit whose rate is 0 is not enforced.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitConfig {
+   /// How many plays a single member can make in quick succession
+   pub user_burst: u32,
+   /// How many plays per minute a single member regains
+   pub user_per_minute: u32,
+   /// How many plays the whole guild can make in quick succession
+   pub guild_burst: u32,
+   /// How many plays per minute the whole guild regains
+   pub guild_per_minute: u32,
+}
+
+impl Default for RateLimitConfig {
+   fn default() -> Self {
+      Self {
+         user_burst: 5,
+         user_per_minute: 10,
+         guild_burst: 15,
+         guild_per_minute: 60,
+      }
+   }
+}
+
+impl RateLimitConfig {
+   pub fn problems(&self) -> Vec<String> {
+      let mut problems = Vec::new();
+      if self.user_per_minute > 0 && self.user_burst == 0 {
+         problems.push("`rate_limit.user_burst` must be at least 1 when `user_per_minute` is set".to_string());
+      }
+      if self.guild_per_minute > 0 && self.guild_burst == 0 {
+         problems.push("`rate_limit.guild_burst` must be at least 1 when `guild_per_minute` is set".to_string());
+      }
+      problems
+   }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum BucketKey {
+   User(GuildId, UserId),
+   Guild(GuildId),
+}
+
+struct Bucket {
+   tokens: f64,
+   updated: Instant,
+   /// When the bucket will have refilled to its burst, after which it is no different from a new one
+   full: Instant,
+}
+
+impl Bucket {
+   /// Tops up the bucket for the time since it was last used, returning how long until it holds a whole token.
+   fn refill(&mut self, burst: u32, per_minute: u32, now: Instant) -> Duration {
+      let per_second = f64::from(per_minute) / 60.0;
+      let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+      self.tokens = (self.tokens + elapsed * per_second).min(f64::from(burst));
+      self.updated = now;
+      if self.tokens >= 1.0 {
+         Duration::ZERO
+      } else {
+         Duration::from_secs_f64((1.0 - self.tokens) / per_second)
+      }
+   }
+
+   /// Takes a play from the bucket, which must have been refilled at `now`.
+   fn take(&mut self, burst: u32, per_minute: u32, now: Instant) {
+      self.tokens -= 1.0;
+      let per_second = f64::from(per_minute) / 60.0;
+      self.full = now + Duration::from_secs_f64((f64::from(burst) - self.tokens) / per_second);
+   }
+}
+
+/// Limits how quickly clips can be played, both per member and per guild.
+#[derive(Default)]
+pub struct RateLimiter {
+   buckets: Mutex<HashMap<BucketKey, Bucket>>,
+}
+
+impl TypeMapKey for RateLimiter {
+   type Value = Arc<RateLimiter>;
+}
+
+impl RateLimiter {
+   /// Takes a play from the member's and the guild's buckets, or returns how long to wait if either is empty.
+   pub fn check(&self, guild_id: GuildId, user_id: UserId, config: &RateLimitConfig) -> Result<(), Duration> {
+      self.check_at(guild_id, user_id, config, Instant::now())
+   }
+
+   fn check_at(
+      &self, guild_id: GuildId, user_id: UserId, config: &RateLimitConfig, now: Instant,
+   ) -> Result<(), Duration> {
+      let limits = [
+         (
+            BucketKey::User(guild_id, user_id),
+            config.user_burst,
+            config.user_per_minute,
+         ),
+         (BucketKey::Guild(guild_id), config.guild_burst, config.guild_per_minute),
+      ];
+      let mut buckets = self.buckets.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+      // forget buckets that have refilled so members and guilds that stopped playing don't stay in memory
+      buckets.retain(|_, bucket| bucket.full > now);
+
+      let mut wait = Duration::ZERO;
+      for (key, burst, per_minute) in limits.iter().filter(|(_, _, per_minute)| *per_minute > 0) {
+         let bucket = buckets.entry(*key).or_insert(Bucket {
+            tokens: f64::from(*burst),
+            updated: now,
+            full: now,
+         });
+         wait = wait.max(bucket.refill(*burst, *per_minute, now));
+      }
+      if !wait.is_zero() {
+         return Err(wait);
+      }
+
+      for (key, burst, per_minute) in limits.iter().filter(|(_, _, per_minute)| *per_minute > 0) {
+         if let Some(bucket) = buckets.get_mut(key) {
+            bucket.take(*burst, *per_minute, now);
+         }
+      }
+      Ok(())
+   }
+}
+
+pub async fn get(ctx: &Context) -> Arc<RateLimiter> {
+   ctx.data
+      .read()
+      .await
+      .get::<RateLimiter>()
+      .cloned()
+      .expect("Rate limiter should have been placed during initialization")
+}
+
+/// Explains a rate limit to the user, rounding the wait up to whole seconds.
+pub fn wait_message(wait: Duration) -> Message {
+   match wait.as_secs_f64().ceil().max(1.0) as u64 {
+      1 => Message::new("rate_limited_one"),
+      seconds => Message::new("rate_limited").with("seconds", seconds),
+   }
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   const GUILD: GuildId = GuildId::new(1);
+
+   fn config(user_burst: u32, user_per_minute: u32, guild_burst: u32, guild_per_minute: u32) -> RateLimitConfig {
+      RateLimitConfig {
+         user_burst,
+         user_per_minute,
+         guild_burst,
+         guild_per_minute,
+      }
+   }
+
+   #[test]
+   fn test_user_bucket_empties_and_refills() {
+      let limiter = RateLimiter::default();
+      let config = config(2, 60, 100, 600);
+      let start = Instant::now();
+      let user = UserId::new(1);
+
+      assert!(limiter.check_at(GUILD, user, &config, start).is_ok());
+      assert!(limiter.check_at(GUILD, user, &config, start).is_ok());
+      let wait = limiter.check_at(GUILD, user, &config, start).unwrap_err();
+      assert_eq!(wait.as_secs_f64().round(), 1.0);
+
+      assert!(limiter.check_at(GUILD, UserId::new(2), &config, start).is_ok());
+      assert!(limiter
+         .check_at(GUILD, user, &config, start + Duration::from_secs(1))
+         .is_ok());
+   }
+
+   #[test]
+   fn test_guild_bucket_shared_by_users() {
+      let limiter = RateLimiter::default();
+      let config = config(10, 60, 2, 6);
+      let now = Instant::now();
+
+      assert!(limiter.check_at(GUILD, UserId::new(1), &config, now).is_ok());
+      assert!(limiter.check_at(GUILD, UserId::new(2), &config, now).is_ok());
+      let wait = limiter.check_at(GUILD, UserId::new(3), &config, now).unwrap_err();
+      assert_eq!(wait.as_secs_f64().round(), 10.0);
+      assert!(limiter.check_at(GuildId::new(2), UserId::new(3), &config, now).is_ok());
+   }
+
+   #[test]
+   fn test_denied_play_takes_no_tokens() {
+      let limiter = RateLimiter::default();
+      let config = config(1, 60, 1, 60);
+      let now = Instant::now();
+
+      assert!(limiter.check_at(GUILD, UserId::new(1), &config, now).is_ok());
+      assert!(limiter.check_at(GUILD, UserId::new(2), &config, now).is_err());
+      // the guild bucket is what denied user 2, so their own bucket should still be full once it refills
+      let later = now + Duration::from_secs(1);
+      assert!(limiter.check_at(GUILD, UserId::new(2), &config, later).is_ok());
+   }
+
+   #[test]
+   fn test_refilled_buckets_forgotten() {
+      let limiter = RateLimiter::default();
+      let config = config(2, 60, 4, 60);
+      let now = Instant::now();
+
+      assert!(limiter.check_at(GUILD, UserId::new(1), &config, now).is_ok());
+      assert!(limiter.check_at(GUILD, UserId::new(2), &config, now).is_ok());
+      assert_eq!(limiter.buckets.lock().unwrap().len(), 3);
+
+      // user 1's bucket refills after a second, but the guild's needs two to get both plays back
+      let later = now + Duration::from_secs(1);
+      assert!(limiter.check_at(GUILD, UserId::new(2), &config, later).is_ok());
+      let buckets = limiter.buckets.lock().unwrap();
+      assert_eq!(buckets.len(), 2);
+      assert!(!buckets.contains_key(&BucketKey::User(GUILD, UserId::new(1))));
+   }
+
+   #[test]
+   fn test_zero_rate_disables_limit() {
+      let limiter = RateLimiter::default();
+      let config = config(0, 0, 0, 0);
+
+      for _ in 0..100 {
+         assert!(limiter.check(GUILD, UserId::new(1), &config).is_ok());
+      }
+   }
+
+   #[test]
+   fn test_wait_message_rounds_up() {
+      assert_eq!(
+         wait_message(Duration::from_millis(200)).to_string(),
+         "You're playing clips too quickly, try again in 1 second"
+      );
+      assert_eq!(
+         wait_message(Duration::from_millis(2100)).to_string(),
+         "You're playing clips too quickly, try again in 3 seconds"
+      );
+   }
+}
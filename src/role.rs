@@ -1,31 +1,46 @@
 use log::error;
-use serenity::{builder::EditRole, client::Context, model::prelude::GuildId};
+use serenity::{
+   builder::EditRole,
+   client::Context,
+   model::{guild::Member, prelude::GuildId},
+};
 use std::{
    fs::File,
    io::{ErrorKind, Read, Write},
-   path::PathBuf,
+   path::{Path, PathBuf},
 };
 
-pub async fn create_admin_role(ctx: &Context, guild_id: &GuildId, mut path: PathBuf) {
-   path.push(".role_id");
+use crate::audio::audio_source;
 
-   let admin_role_id;
-   {
-      let mut admin_role_data = String::new();
-      if let Err(err) = File::open(&path).map(|mut file| file.read_to_string(&mut admin_role_data)) {
-         if err.kind() != ErrorKind::NotFound {
-            error!("Could not retrieve role ID for guild {:?}: {:?}", guild_id, err);
-         }
-      }
-      if admin_role_data.is_empty() {
-         admin_role_id = None;
-      } else {
-         admin_role_id = admin_role_data
-            .parse::<u64>()
-            .map_err(|err| error!("Could not parse .role_id for {:?}: {:?}", guild_id, err))
-            .ok()
+fn read_admin_role_id(guild_id: &GuildId, path: &Path) -> Option<u64> {
+   let mut admin_role_data = String::new();
+   if let Err(err) = File::open(path).map(|mut file| file.read_to_string(&mut admin_role_data)) {
+      if err.kind() != ErrorKind::NotFound {
+         error!("Could not retrieve role ID for guild {:?}: {:?}", guild_id, err);
       }
    }
+   if admin_role_data.is_empty() {
+      None
+   } else {
+      admin_role_data
+         .parse::<u64>()
+         .map_err(|err| error!("Could not parse .role_id for {:?}: {:?}", guild_id, err))
+         .ok()
+   }
+}
+
+pub fn admin_role_id(guild_id: &GuildId) -> Option<u64> {
+   read_admin_role_id(guild_id, &audio_source::guild_dir(guild_id).join(".role_id"))
+}
+
+pub fn is_admin(guild_id: &GuildId, member: &Member) -> bool {
+   admin_role_id(guild_id).is_some_and(|admin_role_id| member.roles.iter().any(|role| *role == admin_role_id))
+}
+
+pub async fn create_admin_role(ctx: &Context, guild_id: &GuildId, mut path: PathBuf) {
+   path.push(".role_id");
+
+   let admin_role_id = read_admin_role_id(guild_id, &path);
 
    if admin_role_id.is_none()
       || !guild_id
@@ -0,0 +1,110 @@
+use std::collections::HashSet;
+
+use crate::audio::audio_source::ClipInfo;
+
+// Dice similarity of trigram sets below which a clip isn't considered a match
+const MIN_SIMILARITY: f32 = 0.3;
+
+/// Finds the clips whose name or tags resemble the query, best matches first.
+///
+/// Names and tags are compared by trigram similarity so that typos and partial words still match, and any that
+/// contain the query outright rank above those that only resemble it.
+pub fn rank(clips: Vec<ClipInfo>, query: &str) -> Vec<ClipInfo> {
+   let query = query.trim().to_lowercase();
+   if query.is_empty() {
+      return clips;
+   }
+   let query_trigrams = trigrams(&query);
+
+   let mut scored = clips
+      .into_iter()
+      .filter_map(|clip| {
+         let score = std::iter::once(&clip.name)
+            .chain(&clip.tags)
+            .map(|field| score(field, &query, &query_trigrams))
+            .fold(0.0, f32::max);
+         (score >= MIN_SIMILARITY).then_some((score, clip))
+      })
+      .collect::<Vec<_>>();
+   scored.sort_by(|(a_score, a), (b_score, b)| b_score.total_cmp(a_score).then_with(|| a.name.cmp(&b.name)));
+   scored.into_iter().map(|(_, clip)| clip).collect()
+}
+
+fn score(field: &str, query: &str, query_trigrams: &HashSet<String>) -> f32 {
+   let field = field.to_lowercase();
+   let field_trigrams = trigrams(&field);
+   let shared = query_trigrams.intersection(&field_trigrams).count();
+   let similarity = 2.0 * shared as f32 / (query_trigrams.len() + field_trigrams.len()).max(1) as f32;
+   if field.contains(query) {
+      1.0 + similarity
+   } else {
+      similarity
+   }
+}
+
+/// Splits text into words and collects every three-character window of each, padded so word edges count too.
+fn trigrams(text: &str) -> HashSet<String> {
+   text
+      .split(|c: char| !c.is_alphanumeric())
+      .filter(|word| !word.is_empty())
+      .flat_map(|word| {
+         let padded = format!("  {word} ").chars().collect::<Vec<_>>();
+         padded
+            .windows(3)
+            .map(|window| window.iter().collect())
+            .collect::<Vec<_>>()
+      })
+      .collect()
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   fn clip(name: &str, tags: &[&str]) -> ClipInfo {
+      ClipInfo {
+         name: name.to_string(),
+         tags: tags.iter().map(|tag| tag.to_string()).collect(),
+         ..Default::default()
+      }
+   }
+
+   fn names(clips: Vec<ClipInfo>) -> Vec<String> {
+      clips.into_iter().map(|clip| clip.name).collect()
+   }
+
+   #[test]
+   fn test_typos_match() {
+      let clips = vec![clip("airhorn", &[]), clip("bruh", &[]), clip("sad_trombone", &[])];
+
+      assert_eq!(names(rank(clips.clone(), "airhron")), ["airhorn"]);
+      assert_eq!(names(rank(clips, "trombon")), ["sad_trombone"]);
+   }
+
+   #[test]
+   fn test_tags_match() {
+      let clips = vec![clip("wow", &["funny", "owen wilson"]), clip("bruh", &["sigh"])];
+
+      assert_eq!(names(rank(clips.clone(), "funy")), ["wow"]);
+      assert_eq!(names(rank(clips, "wilson")), ["wow"]);
+   }
+
+   #[test]
+   fn test_closer_matches_rank_first() {
+      let clips = vec![
+         clip("airhorn", &[]),
+         clip("bruh", &[]),
+         clip("horns", &[]),
+         clip("hornet", &[]),
+      ];
+
+      assert_eq!(names(rank(clips, "horn")), ["horns", "hornet", "airhorn"]);
+   }
+
+   #[test]
+   fn test_empty_query_keeps_everything() {
+      let clips = vec![clip("b", &[]), clip("a", &[])];
+
+      assert_eq!(names(rank(clips, " ")), ["b", "a"]);
+   }
+}
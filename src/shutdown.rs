@@ -0,0 +1,59 @@
+use log::{error, info};
+use serenity::gateway::ShardManager;
+use songbird::Songbird;
+use std::{
+   sync::atomic::{AtomicBool, Ordering},
+   time::Duration,
+};
+use tokio::time::{self, Instant};
+
+use crate::audio::tracks::PlayingTracks;
+
+// How often to check whether the last tracks have finished while waiting on them
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+// Set once shutdown begins, after which nothing new is played
+static IN_PROGRESS: AtomicBool = AtomicBool::new(false);
+
+pub fn in_progress() -> bool {
+   IN_PROGRESS.load(Ordering::SeqCst)
+}
+
+/// Waits until the process is asked to stop with SIGINT (Ctrl-C) or SIGTERM.
+pub async fn signal() {
+   #[cfg(unix)]
+   {
+      use tokio::signal::unix::{signal, SignalKind};
+
+      let mut terminate = signal(SignalKind::terminate()).expect("Could not listen for SIGTERM");
+      tokio::select! {
+         _ = tokio::signal::ctrl_c() => (),
+         _ = terminate.recv() => (),
+      }
+   }
+   #[cfg(not(unix))]
+   if let Err(err) = tokio::signal::ctrl_c().await {
+      error!("Could not listen for Ctrl-C: {:?}", err);
+      std::future::pending::<()>().await;
+   }
+}
+
+/// Stops accepting plays, gives whatever is playing up to the grace period to finish, then leaves every voice
+/// channel and disconnects from the gateway.
+pub async fn run(songbird: &Songbird, tracks: &PlayingTracks, shard_manager: &ShardManager, grace: Duration) {
+   IN_PROGRESS.store(true, Ordering::SeqCst);
+
+   let deadline = Instant::now() + grace;
+   while Instant::now() < deadline && tracks.any_playing().await {
+      time::sleep(POLL_INTERVAL).await;
+   }
+
+   let guild_ids = songbird.iter().map(|(guild_id, _)| guild_id).collect::<Vec<_>>();
+   info!("Leaving voice in {} server(s)", guild_ids.len());
+   for guild_id in guild_ids {
+      if let Err(err) = songbird.leave(guild_id).await {
+         error!("Could not leave voice in {}: {:?}", guild_id.0, err);
+      }
+   }
+   shard_manager.shutdown_all().await;
+}